@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+use crate::input::{Input, Button};
+
+//maps physical buttons to named actions, call update once per frame
+pub struct ActionMap
+{
+    bindings: Vec<(String, Vec<Button>)>,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+}
+
+impl ActionMap
+{
+    pub fn new() -> Self
+    {
+        Self { bindings: Vec::new(), held: HashSet::new(), pressed: HashSet::new(), released: HashSet::new() }
+    }
+
+    pub fn bind(&mut self, action: &str, button: Button)
+    {
+        match self.bindings.iter_mut().find(|(name, _)| name == action)
+        {
+            Some((_, buttons)) => if !buttons.contains(&button) { buttons.push(button); },
+            None => self.bindings.push((action.to_string(), vec![button])),
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, button: Button)
+    {
+        if let Some((_, buttons)) = self.bindings.iter_mut().find(|(name, _)| name == action) { buttons.retain(|b| *b != button); }
+    }
+
+    pub fn clear_bindings(&mut self, action: &str)
+    {
+        self.bindings.retain(|(name, _)| name != action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Button]
+    {
+        self.bindings.iter().find(|(name, _)| name == action).map(|(_, buttons)| &buttons[..]).unwrap_or(&[])
+    }
+
+    pub fn update(&mut self, input: &Input)
+    {
+        self.pressed.clear();
+        self.released.clear();
+        for &(button, pressed) in input.buttons()
+        {
+            if pressed
+            {
+                if self.held.insert(button) { self.pressed.insert(button); }
+            } else if self.held.remove(&button) { self.released.insert(button); }
+        }
+    }
+
+    //releases everything, e.g. on focus loss
+    pub fn release_all(&mut self)
+    {
+        self.released.extend(self.held.drain());
+    }
+
+    pub fn is_action_down(&self, action: &str) -> bool
+    {
+        self.bindings(action).iter().any(|button| self.held.contains(button))
+    }
+
+    pub fn action_pressed(&self, action: &str) -> bool
+    {
+        self.bindings(action).iter().any(|button| self.pressed.contains(button))
+    }
+
+    pub fn action_released(&self, action: &str) -> bool
+    {
+        self.bindings(action).iter().any(|button| self.released.contains(button))
+    }
+
+    #[cfg(feature = "storage")]
+    pub fn save(&self, storage: &mut crate::storage::Storage, key: &str)
+    {
+        storage.set(key, Some(&serde_json::to_string(&self.bindings).unwrap()));
+    }
+
+    //keeps the current bindings if nothing valid is stored
+    #[cfg(feature = "storage")]
+    pub fn load(&mut self, storage: &crate::storage::Storage, key: &str)
+    {
+        if let Some(bindings) = storage.get(key).and_then(|data| serde_json::from_str(&data).ok()) { self.bindings = bindings; }
+    }
+}
+
+impl Default for ActionMap
+{
+    fn default() -> Self { Self::new() }
+}
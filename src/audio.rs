@@ -0,0 +1,174 @@
+use std::{io::{Read, Seek}, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}, time::Duration};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+use rodio::{Source, Decoder, MixerDeviceSink, mixer::Mixer};
+
+const VOLUME_POLL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ducking
+{
+    pub volume: f32, //volume factor while unfocused
+    pub duration: f32, //ramp time in secs
+}
+
+//handle of a stream started with Audio::play_stream, dropping it keeps the stream playing
+#[derive(Clone, Default)]
+pub struct Stream
+{
+    stopped: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    done: Arc<AtomicBool>,
+}
+
+impl Stream
+{
+    pub fn stop(&self) { self.stopped.store(true, Ordering::Relaxed); }
+    pub fn set_paused(&self, paused: bool) { self.paused.store(paused, Ordering::Relaxed); }
+    pub fn is_paused(&self) -> bool { self.paused.load(Ordering::Relaxed) }
+    pub fn is_done(&self) -> bool { self.done.load(Ordering::Relaxed) } //stopped or end of track
+}
+
+//duck ramp evaluated by wall clock on the audio side, so it finishes without frames, e.g. while occluded
+struct Ramp
+{
+    master: f32,
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: f32, //secs for a ramp of 1
+}
+
+impl Ramp
+{
+    fn duck(&self, now: Instant) -> f32
+    {
+        if self.duration <= 0.0 { return self.to; }
+        let step = now.duration_since(self.start).as_secs_f32() / self.duration;
+        if self.from < self.to { (self.from + step).min(self.to) } else { (self.from - step).max(self.to) }
+    }
+
+    fn output(&self, now: Instant) -> f32 { self.master * self.duck(now) }
+}
+
+pub struct Audio
+{
+    sink: MixerDeviceSink,
+    ramp: Arc<Mutex<Ramp>>,
+    ducking: Option<Ducking>,
+    focused: bool,
+}
+
+impl Audio
+{
+    pub(crate) fn new(sink: MixerDeviceSink, ducking: Option<Ducking>) -> Self
+    {
+        let ramp = Ramp { master: 1.0, from: 1.0, to: 1.0, start: Instant::now(), duration: 0.0 };
+        Self { sink, ramp: Arc::new(Mutex::new(ramp)), ducking, focused: true }
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool)
+    {
+        self.focused = focused;
+        self.retarget();
+    }
+
+    //ramps from the current duck towards the one of focus and ducking
+    fn retarget(&mut self)
+    {
+        let to = match self.ducking
+        {
+            Some(ducking) if !self.focused => ducking.volume,
+            _ => 1.0,
+        };
+        let now = Instant::now();
+        let mut ramp = self.ramp.lock().unwrap();
+        ramp.from = ramp.duck(now);
+        ramp.to = to;
+        ramp.start = now;
+        ramp.duration = self.ducking.map_or(0.0, |ducking| ducking.duration);
+    }
+
+    pub fn sink(&self) -> &MixerDeviceSink { &self.sink }
+    pub fn mixer(&self) -> &Mixer { self.sink.mixer() }
+    pub fn volume(&self) -> f32 { self.ramp.lock().unwrap().master }
+    pub fn set_volume(&mut self, volume: f32) { self.ramp.lock().unwrap().master = volume.max(0.0); }
+    pub fn ducking(&self) -> Option<Ducking> { self.ducking }
+    pub fn set_ducking(&mut self, ducking: Option<Ducking>)
+    {
+        self.ducking = ducking;
+        self.retarget();
+    }
+
+    //plays source scaled by master volume and focus ducking
+    pub fn play(&self, source: impl Source + Send + 'static)
+    {
+        let ramp = self.ramp.clone();
+        let output = move || ramp.lock().unwrap().output(Instant::now());
+        let source = source
+            .amplify(output())
+            .periodic_access(VOLUME_POLL, move |source| source.set_factor(output()));
+        self.sink.mixer().add(source);
+    }
+
+    //decodes on the fly while playing instead of up front, for music, e.g. play_stream(std::io::Cursor::new(bytes), true)
+    //wasm: also decoded by rodio and mixed into the same web audio output
+    pub fn play_stream<R: Read + Seek + Send + Sync + 'static>(&self, data: R, looped: bool) -> crate::Result<Stream>
+    {
+        let stream = Stream::default();
+        if looped { self.play_controlled(Decoder::new_looped(data)?, stream.clone()); }
+        else { self.play_controlled(Decoder::new(data)?, stream.clone()); }
+        Ok(stream)
+    }
+
+    fn play_controlled(&self, source: impl Source + Send + 'static, stream: Stream)
+    {
+        let done = stream.done.clone();
+        let source = source
+            .pausable(false)
+            .stoppable()
+            .periodic_access(VOLUME_POLL, move |source|
+            {
+                if stream.stopped.load(Ordering::Relaxed) { source.stop(); }
+                source.inner_mut().set_paused(stream.paused.load(Ordering::Relaxed));
+            })
+            .with_end(move || done.store(true, Ordering::Relaxed));
+        self.play(source);
+    }
+}
+
+//flags the end of the inner source once it runs dry
+struct WithEnd<S, F: FnMut()>
+{
+    inner: S,
+    on_end: Option<F>,
+}
+
+trait SourceExt: Source + Sized
+{
+    fn with_end<F: FnMut()>(self, on_end: F) -> WithEnd<Self, F> { WithEnd { inner: self, on_end: Some(on_end) } }
+}
+
+impl<S: Source> SourceExt for S {}
+
+impl<S: Source, F: FnMut()> Iterator for WithEnd<S, F>
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        let sample = self.inner.next();
+        if sample.is_none() && let Some(mut on_end) = self.on_end.take() { on_end(); }
+        sample
+    }
+}
+
+impl<S: Source, F: FnMut()> Source for WithEnd<S, F>
+{
+    fn current_span_len(&self) -> Option<usize> { self.inner.current_span_len() }
+    fn channels(&self) -> rodio::ChannelCount { self.inner.channels() }
+    fn sample_rate(&self) -> rodio::SampleRate { self.inner.sample_rate() }
+    fn total_duration(&self) -> Option<Duration> { self.inner.total_duration() }
+}
@@ -1,7 +1,9 @@
 use super::*;
 
-pub fn init_logging()
+pub fn init_logging(error_element: Option<&'static str>)
 {
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = error_element;
     #[cfg(not(target_arch = "wasm32"))]
     {
         #[cfg(target_os = "linux")]
@@ -11,19 +13,30 @@ pub fn init_logging()
     }
     #[cfg(target_arch = "wasm32")]
     {
-        std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+        std::panic::set_hook(Box::new(move |info|
+        {
+            console_error_panic_hook::hook(info);
+            if let Some(id) = error_element { report_error(id, &info.to_string()); }
+        }));
         console_log::init().unwrap();
     }
 }
 
-pub fn create_window(event_loop: &ActiveEventLoop) -> Window
+//shows the message in the dom element with the id and unhides it
+#[cfg(target_arch = "wasm32")]
+pub fn report_error(id: &str, message: &str)
+{
+    let Some(element) = web_sys::window().and_then(|window| window.document()).and_then(|document| document.get_element_by_id(id)) else { return; };
+    element.set_text_content(Some(&format!("Something went wrong.\n{message}")));
+    element.remove_attribute("hidden").ok();
+}
+
+pub fn create_window(event_loop: &ActiveEventLoop, attribs: winit::window::WindowAttributes) -> Window
 {
     #[cfg(not(target_arch = "wasm32"))]
     {
         #[allow(unused_mut)]
-        let mut attribs = Window::default_attributes()
-            .with_visible(false)
-            .with_resizable(true);
+        let mut attribs = attribs.with_visible(false); //shown according to App::WINDOW_VISIBILITY
         #[cfg(target_os = "windows")]
         {
             use winit::platform::windows::WindowAttributesExtWindows;
@@ -36,11 +49,12 @@ pub fn create_window(event_loop: &ActiveEventLoop) -> Window
         use winit::platform::web::WindowAttributesExtWebSys;
         use wasm_bindgen::JsCast;
         let web_window = web_sys::window().unwrap();
-        let canvas: web_sys::HtmlCanvasElement = web_window
-            .document().unwrap()
+        let document = web_window.document().unwrap();
+        let canvas: web_sys::HtmlCanvasElement = document
             .get_element_by_id("canvas").unwrap()
             .dyn_into().unwrap();
-        let attribs = Window::default_attributes().with_canvas(Some(canvas));
+        if attribs.title != Window::default_attributes().title { document.set_title(&attribs.title); }
+        let attribs = attribs.with_canvas(Some(canvas));
         event_loop.create_window(attribs).unwrap()
     }
 }
@@ -48,17 +62,34 @@ pub fn create_window(event_loop: &ActiveEventLoop) -> Window
 #[cfg(not(target_arch = "wasm32"))]
 pub mod time
 {
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, PartialOrd)]
     pub struct Instant(std::time::Instant);
     pub fn now() -> Instant { Instant(std::time::Instant::now()) }
     pub fn duration_secs(first: Instant, second: Instant) -> f32 { (second.0 - first.0).as_secs_f32() }
+    pub fn after(instant: Instant, secs: f32) -> Instant { Instant(instant.0 + std::time::Duration::from_secs_f32(secs.max(0.0))) }
+    pub(crate) fn to_winit(instant: Instant) -> std::time::Instant { instant.0 }
+    //sleeps until shortly before instant and spins the rest, plain sleeps overshoot by up to a scheduler tick
+    pub(crate) fn sleep_until(instant: Instant)
+    {
+        const SPIN: std::time::Duration = std::time::Duration::from_millis(2);
+        let now = std::time::Instant::now();
+        if instant.0 > now + SPIN { std::thread::sleep(instant.0 - now - SPIN); }
+        while std::time::Instant::now() < instant.0 { std::hint::spin_loop(); }
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 pub mod time
 {
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, PartialOrd)]
     pub struct Instant(f64);
     pub fn now() -> Instant { Instant(web_sys::window().unwrap().performance().unwrap().now()) }
     pub fn duration_secs(first: Instant, second: Instant) -> f32 { ((second.0 - first.0) / 1e3) as f32 }
+    pub fn after(instant: Instant, secs: f32) -> Instant { Instant(instant.0 + secs.max(0.0) as f64 * 1e3) }
+    pub(crate) fn to_winit(instant: Instant) -> web_time::Instant
+    {
+        let now = web_time::Instant::now();
+        let ms = instant.0 - self::now().0;
+        if ms > 0.0 { now + std::time::Duration::from_secs_f64(ms / 1e3) } else { now }
+    }
 }
@@ -0,0 +1,24 @@
+struct VSOutput
+{
+    @builtin(position) pos: vec4<f32>,
+    @location(0) coords: vec2<f32>,
+}
+
+//fullscreen triangle, counter-clockwise
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VSOutput
+{
+    var uv = vec2<f32>(f32(index & 2u), f32((index << 1u) & 2u));
+    return VSOutput(vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0), uv);
+}
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+
+@fragment
+fn fs_main(in: VSOutput) -> @location(0) vec4<f32>
+{
+    return textureSample(t_source, s_source, in.coords);
+}
@@ -0,0 +1,51 @@
+use std::cell::RefCell;
+
+//high level operation issued by the crate helpers
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op
+{
+    Pass { label: Option<String>, clears: Vec<Option<[f64; 4]>>, depth_clear: Option<f32> }, //None loads
+    Ui { indices: u32 },
+    Sprites { instances: u32, draws: u32 },
+    Blit,
+    Present,
+}
+
+thread_local!
+{
+    static OPS: RefCell<Option<Vec<Op>>> = const { RefCell::new(None) };
+}
+
+//starts recording on this thread, discarding earlier ops
+pub fn start() { OPS.with(|ops| *ops.borrow_mut() = Some(Vec::new())); }
+
+//stops recording and returns the ops
+pub fn stop() -> Vec<Op> { OPS.with(|ops| ops.borrow_mut().take()).unwrap_or_default() }
+
+pub(crate) fn record(op: impl FnOnce() -> Op)
+{
+    OPS.with(|ops| if let Some(ops) = ops.borrow_mut().as_mut() { ops.push(op()); });
+}
+
+//one op per line, for storing baselines
+pub fn to_text(ops: &[Op]) -> String
+{
+    ops.iter().map(|op| format!("{op:?}\n")).collect()
+}
+
+//Err(line, expected, actual) at the first difference to the baseline text
+pub fn compare(baseline: &str, ops: &[Op]) -> Result<(), (usize, Option<String>, Option<String>)>
+{
+    let actual = to_text(ops);
+    let (mut expected, mut actual) = (baseline.lines(), actual.lines());
+    for line in 0..
+    {
+        match (expected.next(), actual.next())
+        {
+            (None, None) => return Ok(()),
+            (e, a) if e == a => {},
+            (e, a) => return Err((line, e.map(str::to_string), a.map(str::to_string))),
+        }
+    }
+    unreachable!()
+}
@@ -0,0 +1,173 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::RefCell;
+#[cfg(target_arch = "wasm32")]
+use std::{rc::Rc, cell::{Cell, RefCell}};
+
+pub type Image = (Vec<u8>, u32, u32); //rgba, width, height
+
+pub struct Clipboard
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: RefCell<Option<arboard::Clipboard>>,
+    #[cfg(target_arch = "wasm32")]
+    image: Rc<RefCell<Option<Image>>>,
+    #[cfg(target_arch = "wasm32")]
+    pending: Rc<Cell<bool>>,
+    #[cfg(target_arch = "wasm32")]
+    text: Rc<RefCell<Option<String>>>,
+    #[cfg(target_arch = "wasm32")]
+    text_pending: Rc<Cell<bool>>,
+    #[cfg(target_arch = "wasm32")]
+    #[cfg_attr(not(feature = "ui"), allow(unused))]
+    pasted: Rc<RefCell<Vec<String>>>,
+    #[cfg(target_arch = "wasm32")]
+    paste: wasm_bindgen::closure::Closure<dyn FnMut(web_sys::ClipboardEvent)>,
+}
+
+impl Clipboard
+{
+    pub(crate) fn new() -> Self
+    {
+        #[cfg(target_arch = "wasm32")]
+        let (text, pasted) = (Rc::new(RefCell::new(None)), Rc::new(RefCell::new(Vec::new())));
+        #[cfg(target_arch = "wasm32")]
+        let paste =
+        {
+            use wasm_bindgen::JsCast;
+            let (text, pasted) = (text.clone(), pasted.clone());
+            let paste = wasm_bindgen::closure::Closure::<dyn FnMut(web_sys::ClipboardEvent)>::new(move |event: web_sys::ClipboardEvent|
+            {
+                let Some(data) = event.clipboard_data().and_then(|data| data.get_data("text").ok()) else { return; };
+                *text.borrow_mut() = Some(data.clone());
+                pasted.borrow_mut().push(data);
+            });
+            web_sys::window().unwrap().add_event_listener_with_callback("paste", paste.as_ref().unchecked_ref()).unwrap();
+            paste
+        };
+        Self
+        {
+            #[cfg(not(target_arch = "wasm32"))]
+            inner: RefCell::new(arboard::Clipboard::new().ok()),
+            #[cfg(target_arch = "wasm32")]
+            image: Rc::new(RefCell::new(None)),
+            #[cfg(target_arch = "wasm32")]
+            pending: Rc::new(Cell::new(false)),
+            #[cfg(target_arch = "wasm32")]
+            text,
+            #[cfg(target_arch = "wasm32")]
+            text_pending: Rc::new(Cell::new(false)),
+            #[cfg(target_arch = "wasm32")]
+            pasted,
+            #[cfg(target_arch = "wasm32")]
+            paste,
+        }
+    }
+
+    //on wasm the read is async: this returns the latest finished read and starts a new one
+    pub fn image(&self) -> Option<Image>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.inner.borrow_mut().as_mut()?.get_image().ok()
+            .map(|image| (image.bytes.into_owned(), image.width as u32, image.height as u32));
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            if !self.pending.get()
+            {
+                self.pending.set(true);
+                let (image, pending) = (self.image.clone(), self.pending.clone());
+                wasm_bindgen_futures::spawn_local(async move
+                {
+                    *image.borrow_mut() = read_image().await;
+                    pending.set(false);
+                });
+            }
+            return self.image.borrow().clone();
+        }
+    }
+
+    //like image on wasm, paste events update the cached text immediately
+    pub fn text(&self) -> Option<String>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.inner.borrow_mut().as_mut()?.get_text().ok();
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            if !self.text_pending.get()
+            {
+                self.text_pending.set(true);
+                let (text, pending) = (self.text.clone(), self.text_pending.clone());
+                wasm_bindgen_futures::spawn_local(async move
+                {
+                    let read = web_sys::window().unwrap().navigator().clipboard().read_text();
+                    if let Some(read) = wasm_bindgen_futures::JsFuture::from(read).await.ok().and_then(|read| read.as_string()) { *text.borrow_mut() = Some(read); }
+                    pending.set(false);
+                });
+            }
+            return self.text.borrow().clone();
+        }
+    }
+
+    //wasm: async and only allowed during user input
+    pub fn set_text(&self, text: &str)
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(clipboard) = self.inner.borrow_mut().as_mut() && let Err(err) = clipboard.set_text(text) { log::warn!("clipboard: {err}"); }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            *self.text.borrow_mut() = Some(text.to_string());
+            let _ = web_sys::window().unwrap().navigator().clipboard().write_text(text);
+        }
+    }
+
+    //native: the clipboard text if the paste shortcut was pressed, wasm: texts of paste events
+    #[cfg(feature = "ui")]
+    pub(crate) fn take_pasted(&self, shortcut: bool) -> Vec<String>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return if shortcut { self.text().into_iter().collect() } else { Vec::new() };
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = shortcut;
+            return std::mem::take(&mut *self.pasted.borrow_mut());
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Clipboard
+{
+    fn drop(&mut self)
+    {
+        use wasm_bindgen::JsCast;
+        web_sys::window().unwrap().remove_event_listener_with_callback("paste", self.paste.as_ref().unchecked_ref()).ok();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn read_image() -> Option<Image>
+{
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    let window = web_sys::window()?;
+    let items: js_sys::Array = JsFuture::from(window.navigator().clipboard().read()).await.ok()?.dyn_into().ok()?;
+    for item in items.iter()
+    {
+        let item: web_sys::ClipboardItem = item.dyn_into().ok()?;
+        let Some(ty) = item.types().iter().filter_map(|ty| ty.as_string()).find(|ty| ty.starts_with("image/")) else { continue; };
+        let blob: web_sys::Blob = JsFuture::from(item.get_type(&ty)).await.ok()?.dyn_into().ok()?;
+        let bitmap: web_sys::ImageBitmap = JsFuture::from(window.create_image_bitmap_with_blob(&blob).ok()?).await.ok()?.dyn_into().ok()?;
+        let (width, height) = (bitmap.width(), bitmap.height());
+        let canvas: web_sys::HtmlCanvasElement = window.document()?.create_element("canvas").ok()?.dyn_into().ok()?;
+        canvas.set_width(width);
+        canvas.set_height(height);
+        let context: web_sys::CanvasRenderingContext2d = canvas.get_context("2d").ok()??.dyn_into().ok()?;
+        context.draw_image_with_image_bitmap(&bitmap, 0.0, 0.0).ok()?;
+        let data = context.get_image_data(0.0, 0.0, width as f64, height as f64).ok()?.data().0;
+        return Some((data, width, height));
+    }
+    None
+}
@@ -1,14 +1,70 @@
 #[cfg(not(target_arch = "wasm32"))]
 use std::{fs, thread};
-use std::{pin::Pin, task::{self, Poll}, future::Future};
+use std::{pin::Pin, task::{self, Poll}, future::Future, collections::HashMap};
 use crate::{Error, Result};
 
+//retries of failed web requests, network errors and 5xx only, ignored on native
+#[derive(Clone, Copy, Debug)]
+pub struct Retry
+{
+    pub attempts: u32,
+    pub delay: f32, //secs before the first retry
+    pub backoff: f32, //delay factor per further retry
+}
+
+impl Retry
+{
+    pub const NONE: Self = Self { attempts: 0, delay: 0.0, backoff: 1.0 };
+}
+
+//native: CARGO_MANIFEST_DIR under cargo run, else the executable directory
+//wasm: directory of the page url
+pub fn asset_dir() -> std::path::PathBuf
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::env::var_os("CARGO_MANIFEST_DIR").map(Into::into)
+        .or_else(|| std::env::current_exe().ok().and_then(|exe| exe.parent().map(Into::into)))
+        .unwrap_or_else(|| ".".into());
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let base = web_sys::window().and_then(|window| window.document()).and_then(|document| document.base_uri().ok().flatten()).unwrap_or_default();
+        return base[..base.rfind('/').map_or(0, |i| i + 1)].into();
+    }
+}
+
+//asset_dir joined with the relative path, for Loader::load
+pub fn asset_path(path: &str) -> String
+{
+    asset_dir().join(path).to_string_lossy().into_owned()
+}
+
 pub struct File
 {
     #[cfg(not(target_arch = "wasm32"))]
     recv: flume::Receiver<Result<Vec<u8>>>,
     #[cfg(target_arch = "wasm32")]
     request: (web_sys::XmlHttpRequest, bool),
+    #[cfg(target_arch = "wasm32")]
+    path: String,
+    #[cfg(target_arch = "wasm32")]
+    retry: Retry,
+    #[cfg(target_arch = "wasm32")]
+    attempt: u32,
+    #[cfg(target_arch = "wasm32")]
+    retry_at: Option<crate::time::Instant>,
+    #[cfg(target_arch = "wasm32")]
+    bundled: Option<Vec<u8>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn send_request(path: &str) -> web_sys::XmlHttpRequest
+{
+    let request = web_sys::XmlHttpRequest::new().unwrap();
+    request.open_with_async("GET", path, true).unwrap();
+    request.set_response_type(web_sys::XmlHttpRequestResponseType::Arraybuffer);
+    request.send().unwrap();
+    request
 }
 
 impl File
@@ -24,18 +80,35 @@ impl File
         };
 
         #[cfg(target_arch = "wasm32")]
-        return if self.request.1 || self.request.0.ready_state() != 4 { None } //DONE
-        else
         {
-            self.request.1 = true;
-            let status = self.request.0.status().unwrap();
-            if status == 200 //OK
-            {
-                Some(Ok(js_sys::Uint8Array::new_with_byte_offset(&self.request.0.response().unwrap(), 0).to_vec()))
-            } else
+            if let Some(data) = self.bundled.take() { return Some(Ok(data)); }
+            if let Some(retry_at) = self.retry_at
             {
-                Some(Err(Error::Loader("Loading Status not OK")))
+                if crate::time::duration_secs(retry_at, crate::time::now()) < 0.0 { return None; }
+                self.retry_at = None;
+                self.request.0 = send_request(&self.path);
             }
+            return if self.request.1 || self.request.0.ready_state() != 4 { None } //DONE
+            else
+            {
+                let status = self.request.0.status().unwrap();
+                let transient = status == 0 || status >= 500; //network error or server error
+                if status == 200 //OK
+                {
+                    self.request.1 = true;
+                    Some(Ok(js_sys::Uint8Array::new_with_byte_offset(&self.request.0.response().unwrap(), 0).to_vec()))
+                } else if transient && self.attempt < self.retry.attempts
+                {
+                    let delay = self.retry.delay * self.retry.backoff.powi(self.attempt as i32);
+                    self.attempt += 1;
+                    self.retry_at = Some(crate::time::after(crate::time::now(), delay));
+                    None
+                } else
+                {
+                    self.request.1 = true;
+                    Some(Err(Error::Loader("Loading Status not OK")))
+                }
+            };
         }
     }
 }
@@ -44,6 +117,30 @@ pub struct Loader
 {
     #[cfg(not(target_arch = "wasm32"))]
     thread: flume::Sender<(String, flume::Sender<Result<Vec<u8>>>)>,
+    #[cfg_attr(not(target_arch = "wasm32"), allow(unused))]
+    retry: Retry,
+    bundle: HashMap<String, &'static [u8]>,
+}
+
+//strips ./ to match bundle paths
+fn normalize(path: &str) -> &str
+{
+    path.trim_start_matches("./")
+}
+
+//bundle layout: per entry u32 le path length, utf8 path, u64 le data length, data
+pub fn pack_bundle<'a>(entries: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Vec<u8>
+{
+    let mut bundle = Vec::new();
+    for (path, data) in entries
+    {
+        let path = normalize(path);
+        bundle.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        bundle.extend_from_slice(path.as_bytes());
+        bundle.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bundle.extend_from_slice(data);
+    }
+    bundle
 }
 
 impl Loader
@@ -66,11 +163,71 @@ impl Loader
                 });
                 send
             },
+            retry: Retry::NONE,
+            bundle: HashMap::new(),
         }
     }
 
+    //served by load instead of the filesystem or network
+    pub fn mount(&mut self, path: &str, data: &'static [u8])
+    {
+        self.bundle.insert(normalize(path).to_owned(), data);
+    }
+
+    //mounts all entries of a pack_bundle archive, e.g. from include_bytes!
+    pub fn mount_bundle(&mut self, mut bytes: &'static [u8]) -> Result<()>
+    {
+        fn take(bytes: &mut &'static [u8], len: usize) -> Result<&'static [u8]>
+        {
+            if bytes.len() < len { return Err(Error::Loader("truncated bundle")); }
+            let (head, tail) = bytes.split_at(len);
+            *bytes = tail;
+            Ok(head)
+        }
+        while !bytes.is_empty()
+        {
+            let path_len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap()) as usize;
+            let path = std::str::from_utf8(take(&mut bytes, path_len)?).map_err(|_| Error::Loader("bundle path not utf8"))?;
+            let data_len = u64::from_le_bytes(take(&mut bytes, 8)?.try_into().unwrap()) as usize;
+            let data = take(&mut bytes, data_len)?;
+            self.mount(path, data);
+        }
+        Ok(())
+    }
+
+    pub fn with_retry(mut self, retry: Retry) -> Self
+    {
+        self.retry = retry;
+        self
+    }
+
     pub fn load(&mut self, path: &str) -> File
     {
+        if let Some(&data) = self.bundle.get(normalize(path))
+        {
+            return File
+            {
+                #[cfg(not(target_arch = "wasm32"))]
+                recv:
+                {
+                    let (send, recv) = flume::bounded(1);
+                    send.send(Ok(data.to_vec())).unwrap();
+                    recv
+                },
+                #[cfg(target_arch = "wasm32")]
+                request: (web_sys::XmlHttpRequest::new().unwrap(), true), //never sent
+                #[cfg(target_arch = "wasm32")]
+                path: path.to_owned(),
+                #[cfg(target_arch = "wasm32")]
+                retry: Retry::NONE,
+                #[cfg(target_arch = "wasm32")]
+                attempt: 0,
+                #[cfg(target_arch = "wasm32")]
+                retry_at: None,
+                #[cfg(target_arch = "wasm32")]
+                bundled: Some(data.to_vec()),
+            };
+        }
         File
         {
             #[cfg(not(target_arch = "wasm32"))]
@@ -81,14 +238,17 @@ impl Loader
                 recv
             },
             #[cfg(target_arch = "wasm32")]
-            request:
-            {
-                let request = web_sys::XmlHttpRequest::new().unwrap();
-                request.open_with_async("GET", path, true).unwrap();
-                request.set_response_type(web_sys::XmlHttpRequestResponseType::Arraybuffer);
-                request.send().unwrap();
-                (request, false)
-            },
+            request: (send_request(path), false),
+            #[cfg(target_arch = "wasm32")]
+            path: path.to_owned(),
+            #[cfg(target_arch = "wasm32")]
+            retry: self.retry,
+            #[cfg(target_arch = "wasm32")]
+            attempt: 0,
+            #[cfg(target_arch = "wasm32")]
+            retry_at: None,
+            #[cfg(target_arch = "wasm32")]
+            bundled: None,
         }
     }
 }
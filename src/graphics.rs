@@ -1,145 +1,643 @@
-use std::sync::Arc;
+use std::sync::{Arc, atomic::{AtomicBool, AtomicU32, Ordering}};
 use winit::window::Window;
-use crate::{SurfaceError, Error, Result};
+use crate::{App, SurfaceError, BufferError, Error, Result};
+
+pub mod coords;
+mod pass;
+pub use pass::{Pass, attachment_ops};
+mod gbuffer;
+pub use gbuffer::GBuffer;
+mod depth;
+pub use depth::{DepthConfig, DepthBuffer};
+mod readback;
+pub use readback::ReadbackRing;
+mod msaa;
+pub use msaa::{MsaaTarget, create_msaa_target};
+mod target;
+pub use target::RenderTarget;
+mod blit;
+pub use blit::Blitter;
+mod pixel;
+pub use pixel::{RenderResolution, Letterbox};
+mod sprite;
+pub use sprite::{Sprite, SpriteBatch};
+mod rect;
+pub use rect::RectRenderer;
+mod debug_text;
+pub use debug_text::DebugText;
+#[cfg(feature = "image")]
+mod texture;
+#[cfg(feature = "image")]
+pub use texture::{AnimatedTexture, create_texture, load_texture, load_texture_with, LoadOptions, SizePolicy, premultiply, load_gif, load_gif_with, load_sprite_sheet, load_sprite_sheet_with};
+#[cfg(feature = "ktx2")]
+mod ktx2;
+#[cfg(feature = "ktx2")]
+pub use self::ktx2::load_ktx2;
+
+//winding of front faces in all crate pipelines, the fullscreen triangle and quads are counter-clockwise on screen
+pub const FRONT_FACE: wgpu::FrontFace = wgpu::FrontFace::Ccw;
+
+//blending of crate pipelines, Premultiplied expects colors and textures with rgb already multiplied by alpha
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlphaMode
+{
+    Straight,
+    Premultiplied,
+}
+
+impl AlphaMode
+{
+    pub fn blend_state(self) -> wgpu::BlendState
+    {
+        match self
+        {
+            Self::Straight => wgpu::BlendState::ALPHA_BLENDING,
+            Self::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace
+{
+    Srgb,
+    DisplayP3,
+}
+
+#[derive(Clone, Debug)]
+pub struct Config
+{
+    pub backends: wgpu::Backends,
+    pub features: wgpu::Features,
+    pub limits: wgpu::Limits,
+    pub downlevel_flags: wgpu::DownlevelFlags,
+    pub surface_usage: wgpu::TextureUsages, //RENDER_ATTACHMENT is always added
+    pub depth: Option<DepthConfig>,
+    pub sample_count: u32,
+    pub present_mode: wgpu::PresentMode,
+    pub vrr: bool, //overrides present_mode with a low latency mode for variable refresh displays
+    pub color_space: ColorSpace, //wasm canvas only, webgl2 backend only
+    pub srgb_view: bool, //adds an srgb view format to non-srgb surfaces, some webgl2 contexts reject it
+    pub adapter: AdapterPrefs,
+    pub trace_path: Option<std::path::PathBuf>, //native with the trace feature only
+    pub debug_overlay: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AdapterPrefs
+{
+    pub power_preference: wgpu::PowerPreference,
+    pub force_fallback_adapter: bool,
+    pub select: Option<fn(&[wgpu::AdapterInfo]) -> Option<usize>>, //picks from enumerate_adapters, None or an unusable pick falls back to the automatic choice
+}
+
+impl Default for AdapterPrefs
+{
+    fn default() -> Self
+    {
+        Self { power_preference: wgpu::PowerPreference::HighPerformance, force_fallback_adapter: false, select: None }
+    }
+}
+
+//the App trait defaults
+impl Default for Config
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            backends: wgpu::Backends::all(),
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+            downlevel_flags: wgpu::DownlevelFlags::empty(),
+            surface_usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            depth: None,
+            sample_count: 1,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            vrr: false,
+            color_space: ColorSpace::Srgb,
+            srgb_view: true,
+            adapter: AdapterPrefs::default(),
+            trace_path: None,
+            debug_overlay: false,
+        }
+    }
+}
+
+impl Config
+{
+    pub fn from_app<T: App>() -> Self
+    {
+        Self
+        {
+            backends: T::BACKENDS,
+            features: T::FEATURES,
+            limits: T::LIMITS,
+            downlevel_flags: T::REQUIRED_DOWNLEVEL_FLAGS,
+            surface_usage: T::SURFACE_USAGE,
+            depth: T::DEPTH,
+            sample_count: T::SAMPLE_COUNT,
+            present_mode: T::PRESENT_MODE,
+            vrr: T::VRR,
+            color_space: T::COLOR_SPACE,
+            srgb_view: T::SRGB_VIEW,
+            adapter: AdapterPrefs { power_preference: T::POWER_PREFERENCE, force_fallback_adapter: T::FORCE_FALLBACK_ADAPTER, select: T::SELECT_ADAPTER },
+            #[cfg(not(target_arch = "wasm32"))]
+            trace_path: std::env::var_os("GRU_WGPU_TRACE").map(Into::into).or_else(|| T::TRACE_PATH.map(Into::into)),
+            #[cfg(target_arch = "wasm32")]
+            trace_path: None,
+            debug_overlay: T::DEBUG_OVERLAY,
+        }
+    }
+}
+
+//(padded, unpadded) bytes per row, buffer <-> texture copies need the padded row length
+pub fn aligned_bytes_per_row(width: u32, bytes_per_pixel: u32) -> (u32, u32)
+{
+    let unpadded = width * bytes_per_pixel;
+    (unpadded.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT), unpadded)
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen::prelude::wasm_bindgen]
+extern "C"
+{
+    type CanvasContext;
+    #[wasm_bindgen(method, setter = drawingBufferColorSpace)]
+    fn set_drawing_buffer_color_space(this: &CanvasContext, color_space: &str);
+}
+
+//wgpu does not expose the canvas color space, the webgl2 context is patched directly
+#[cfg(target_arch = "wasm32")]
+fn set_canvas_color_space(window: &Window, color_space: ColorSpace)
+{
+    use wasm_bindgen::JsCast;
+    use winit::platform::web::WindowExtWebSys;
+    if color_space == ColorSpace::Srgb { return; }
+    let Some(canvas) = window.canvas() else { return; };
+    match canvas.get_context("webgl2")
+    {
+        Ok(Some(context)) => context.unchecked_into::<CanvasContext>().set_drawing_buffer_color_space("display-p3"),
+        _ => log::warn!("canvas color space {color_space:?} is only supported with webgl2"),
+    }
+}
+
+pub fn create_instance(config: &Config) -> wgpu::Instance
+{
+    let instance_descr = wgpu::InstanceDescriptor
+    {
+        backends: config.backends,
+        flags: wgpu::InstanceFlags::from_build_config(),
+        memory_budget_thresholds: wgpu::MemoryBudgetThresholds
+        {
+            for_resource_creation: None,
+            for_device_loss: None,
+        },
+        backend_options: wgpu::BackendOptions
+        {
+            gl: wgpu::GlBackendOptions
+            {
+                gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
+                fence_behavior: wgpu::GlFenceBehavior::Normal,
+                #[cfg(debug_assertions)]
+                debug_fns: wgpu::GlDebugFns::Auto,
+                #[cfg(not(debug_assertions))]
+                debug_fns: wgpu::GlDebugFns::Disabled,
+            },
+            dx12: wgpu::Dx12BackendOptions
+            {
+                shader_compiler: wgpu::Dx12Compiler::StaticDxc,
+                presentation_system: wgpu::Dx12SwapchainKind::DxgiFromHwnd,
+                latency_waitable_object: wgpu::Dx12UseFrameLatencyWaitableObject::None, //TODO expose this maybe?
+                force_shader_model: wgpu::ForceShaderModelToken::default(),
+                agility_sdk: None,
+            },
+            noop: wgpu::NoopBackendOptions
+            {
+                enable: config.backends.contains(wgpu::Backends::NOOP),
+            },
+        },
+        display: None,
+    };
+    wgpu::Instance::new(instance_descr)
+}
+
+//all adapters of the configured backends, e.g. for a gpu dropdown, (backend, vendor, device, name) identify one across runs
+pub async fn enumerate_adapters(config: &Config) -> Vec<wgpu::AdapterInfo>
+{
+    create_instance(config).enumerate_adapters(config.backends).await.iter().map(wgpu::Adapter::get_info).collect()
+}
+
+//backends as in Config::backends, so the indices of AdapterPrefs::select match enumerate_adapters
+pub async fn request_adapter(instance: &wgpu::Instance, surface: Option<&wgpu::Surface<'_>>, backends: wgpu::Backends, prefs: AdapterPrefs) -> Result<wgpu::Adapter>
+{
+    if let Some(select) = prefs.select
+    {
+        let mut adapters = instance.enumerate_adapters(backends).await;
+        let infos: Vec<_> = adapters.iter().map(wgpu::Adapter::get_info).collect();
+        if let Some(index) = select(&infos)
+        {
+            match index < adapters.len()
+            {
+                true if surface.is_none_or(|surface| adapters[index].is_surface_supported(surface)) => return Ok(adapters.swap_remove(index)),
+                true => log::warn!("selected adapter {} can not present to the surface", infos[index].name),
+                false => log::warn!("selected adapter {index} out of {}", adapters.len()),
+            }
+        }
+    }
+    let adapter_opt = wgpu::RequestAdapterOptions
+    {
+        power_preference: prefs.power_preference,
+        force_fallback_adapter: prefs.force_fallback_adapter,
+        compatible_surface: surface,
+    };
+    match instance.request_adapter(&adapter_opt).await
+    {
+        Ok(adapter) => Ok(adapter),
+        Err(err) => Err(Error::Adapter(err)), //err not Send+Sync on wasm -> no ? operator
+    }
+}
+
+//outcome of current_surface, only Ready yields something to render into
+pub enum FrameAcquire
+{
+    NotConfigured, //no nonzero surface size yet
+    Reconfigured, //surface was suboptimal or outdated and got reconfigured, retry next frame
+    Unavailable, //timeout or occluded window, retry next frame, always for init_noop
+    Busy, //try_acquire_frame only, all swapchain images are still queued and the acquire would block
+    Ready(wgpu::SurfaceTexture, wgpu::TextureView),
+}
+
+impl FrameAcquire
+{
+    pub fn is_ready(&self) -> bool { matches!(self, Self::Ready(..)) }
+
+    pub fn ready(self) -> Option<(wgpu::SurfaceTexture, wgpu::TextureView)>
+    {
+        match self
+        {
+            Self::Ready(texture, view) => Some((texture, view)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryReport
+{
+    pub allocated: u64, //bytes
+    pub capacity: u64, //bytes reserved from the driver
+    pub blocks: Vec<(u64, u64)>, //(size, allocated) per memory block
+}
 
 pub struct Graphics
 {
+    config: Config,
     #[allow(unused)]
     instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     backend: wgpu::Backend,
-    surface: wgpu::Surface<'static>,
+    surface: Option<wgpu::Surface<'static>>, //None for init_noop
     surface_format: wgpu::TextureFormat,
+    surface_usage: wgpu::TextureUsages,
     surface_size: Option<(u32, u32)>,
+    present_modes: Vec<wgpu::PresentMode>,
+    present_mode: wgpu::PresentMode,
     view_format: wgpu::TextureFormat,
+    sample_count: u32,
+    msaa: Option<(wgpu::Texture, wgpu::TextureView)>, //surface sized, resolved into the surface
+    depth: Option<DepthBuffer>, //for Config::depth
+    dummy: Option<(wgpu::Texture, wgpu::TextureView)>, //stands in for the surface without one
+    overlay: Option<debug_text::SharedOverlay>,
+    lost: Arc<AtomicBool>, //set by the device lost callback
+    in_flight: Arc<AtomicU32>, //presented frames whose commands the gpu has not finished
+    suspended: bool, //surface dropped until resume
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
 }
 
 impl Graphics
 {
-    pub(crate) async fn init(backends: wgpu::Backends, features: wgpu::Features, limits: wgpu::Limits, window: Arc<Window>) -> Result<Self>
+    pub(crate) async fn init(config: &Config, window: Arc<Window>) -> Result<Self>
     {
-        let instance_descr = wgpu::InstanceDescriptor
-        {
-            backends,
-            flags: wgpu::InstanceFlags::from_build_config(),
-            memory_budget_thresholds: wgpu::MemoryBudgetThresholds
-            {
-                for_resource_creation: None,
-                for_device_loss: None,
-            },
-            backend_options: wgpu::BackendOptions
-            {
-                gl: wgpu::GlBackendOptions
-                {
-                    gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
-                    fence_behavior: wgpu::GlFenceBehavior::Normal,
-                    #[cfg(debug_assertions)]
-                    debug_fns: wgpu::GlDebugFns::Auto,
-                    #[cfg(not(debug_assertions))]
-                    debug_fns: wgpu::GlDebugFns::Disabled,
-                },
-                dx12: wgpu::Dx12BackendOptions
-                {
-                    shader_compiler: wgpu::Dx12Compiler::StaticDxc,
-                    presentation_system: wgpu::Dx12SwapchainKind::DxgiFromHwnd,
-                    latency_waitable_object: wgpu::Dx12UseFrameLatencyWaitableObject::None, //TODO expose this maybe?
-                    force_shader_model: wgpu::ForceShaderModelToken::default(),
-                    agility_sdk: None,
-                },
-                noop: wgpu::NoopBackendOptions
-                {
-                    enable: false,
-                },
-            },
-            display: None,
-        };
-        let instance = wgpu::Instance::new(instance_descr);
+        let instance = create_instance(config);
 
+        #[cfg(target_arch = "wasm32")]
+        let canvas_window = window.clone();
+        #[cfg(not(target_arch = "wasm32"))]
         let surface = instance.create_surface(window)?;
+        #[cfg(target_arch = "wasm32")]
+        let surface = Self::create_canvas_surface(&instance, window).await?;
         let surface_size = None;
 
-        let adapter_opt = wgpu::RequestAdapterOptions
+        let adapter = request_adapter(&instance, Some(&surface), config.backends, config.adapter).await?;
+        let backend = adapter.get_info().backend;
+        let missing_flags = config.downlevel_flags - adapter.get_downlevel_capabilities().flags;
+        if !missing_flags.is_empty() { return Err(Error::Downlevel(missing_flags)); }
+        let missing_features = config.features - adapter.features();
+        if !missing_features.is_empty() { return Err(Error::Features(missing_features)); }
+        let mut exceeded = Vec::new();
+        config.limits.check_limits_with_fail_fn(&adapter.limits(), false, |name, requested, allowed| exceeded.push((name, requested, allowed)));
+        if !exceeded.is_empty() { return Err(Error::Limits(exceeded)); }
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_usage = config.surface_usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        if !surface_caps.usages.contains(surface_usage) { return Err(Error::SurfaceUsage(surface_usage - surface_caps.usages)); }
+        let surface_format = if surface_usage.contains(wgpu::TextureUsages::STORAGE_BINDING)
+        {
+            //srgb formats can not be storage bound, the srgb view is still available for rendering
+            surface_caps.formats.iter()
+                .copied()
+                .find(|&f| adapter.get_texture_format_features(f).allowed_usages.contains(surface_usage))
+                .ok_or(Error::SurfaceUsage(wgpu::TextureUsages::STORAGE_BINDING))?
+        } else
         {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
+            surface_caps.formats.iter()
+                .copied()
+                .filter(|f| f.is_srgb())
+                .next()
+                .unwrap_or_else(|| surface_caps.formats[0])
         };
-        let adapter = match instance.request_adapter(&adapter_opt).await
+        let present_modes = surface_caps.present_modes.clone();
+        let present_mode = if config.vrr { Self::vrr_present_mode(&present_modes) } else { Self::supported_present_mode(&present_modes, config.present_mode) };
+        let view_format = if config.srgb_view { surface_format.add_srgb_suffix() } else { surface_format };
+        let sample_count = if adapter.get_texture_format_features(view_format).flags.sample_count_supported(config.sample_count) { config.sample_count } else
         {
-            Ok(adapter) => adapter,
-            Err(err) => return Err(Error::Adapter(err)), //err not Send+Sync on wasm -> no ? operator
+            log::warn!("sample count {} unsupported for {view_format:?}, using 1", config.sample_count);
+            1
         };
-        let backend = adapter.get_info().backend;
-
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps.formats.iter()
-            .copied()
-            .filter(|f| f.is_srgb())
-            .next()
-            .unwrap_or_else(|| surface_caps.formats[0]);
-        let view_format = surface_format.add_srgb_suffix();
 
         let device_descr = wgpu::DeviceDescriptor
         {
             label: None,
-            required_features: features,
-            required_limits: limits,
+            required_features: config.features,
+            required_limits: config.limits.clone(),
             experimental_features: wgpu::ExperimentalFeatures::disabled(),
             memory_hints: wgpu::MemoryHints::Performance,
-            trace: wgpu::Trace::Off,
+            trace: Self::trace(config),
         };
         let (device, queue) = match adapter.request_device(&device_descr).await
         {
             Ok(ok) => ok,
             Err(err) => return Err(Error::Device(err)), //err not Send+Sync on wasm -> no ? operator
         };
+        #[cfg(target_arch = "wasm32")]
+        set_canvas_color_space(&canvas_window, config.color_space);
+
+        let lost = Self::watch_device_lost(&device);
+        let mut graphics = Self { config: config.clone(), instance, adapter, backend, surface: Some(surface), surface_format, surface_usage, surface_size, present_modes, present_mode, view_format, sample_count, msaa: None, depth: None, dummy: None, overlay: None, lost, in_flight: Arc::new(AtomicU32::new(0)), suspended: false, device, queue };
+        if config.debug_overlay { graphics.overlay = Some(debug_text::DebugOverlay::new(&graphics)); }
+        Ok(graphics)
+    }
+
+    //headless device on the noop backend for tests without a gpu, commands are accepted and do nothing,
+    //frame() renders into a size sized dummy texture
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn init_noop(size: (u32, u32)) -> Result<Self>
+    {
+        let config = Config
+        {
+            backends: wgpu::Backends::NOOP,
+            surface_usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            present_mode: wgpu::PresentMode::Fifo,
+            ..Default::default()
+        };
+        let instance = create_instance(&config);
+        let adapter = pollster::block_on(request_adapter(&instance, None, config.backends, config.adapter))?;
+        let device_descr = wgpu::DeviceDescriptor
+        {
+            label: None,
+            required_features: config.features,
+            required_limits: config.limits.clone(),
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            memory_hints: wgpu::MemoryHints::Performance,
+            trace: wgpu::Trace::Off,
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&device_descr))?;
+        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let mut graphics = Self
+        {
+            surface_usage: config.surface_usage,
+            config,
+            instance,
+            adapter,
+            backend: wgpu::Backend::Noop,
+            surface: None,
+            surface_format: format,
+            surface_size: None,
+            present_modes: vec![wgpu::PresentMode::Fifo],
+            present_mode: wgpu::PresentMode::Fifo,
+            view_format: format,
+            sample_count: 1,
+            msaa: None,
+            depth: None,
+            dummy: None,
+            overlay: None,
+            lost: Self::watch_device_lost(&device),
+            in_flight: Arc::new(AtomicU32::new(0)),
+            suspended: false,
+            device,
+            queue,
+        };
+        graphics.configure(size);
+        Ok(graphics)
+    }
+
+    //the canvas may not be attached yet when init runs, retries once after a microtask
+    #[cfg(target_arch = "wasm32")]
+    async fn create_canvas_surface(instance: &wgpu::Instance, window: Arc<Window>) -> Result<wgpu::Surface<'static>>
+    {
+        match instance.create_surface(window.clone())
+        {
+            Ok(surface) => Ok(surface),
+            Err(err) =>
+            {
+                log::warn!("create surface: {err}, retrying");
+                let _ = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::resolve(&wasm_bindgen::JsValue::NULL)).await;
+                Ok(instance.create_surface(window)?) //the canvas was still missing after the retry
+            },
+        }
+    }
+
+    //drops the surface, the platform may destroy the window's native surface while suspended
+    pub(crate) fn suspend(&mut self)
+    {
+        if self.surface.is_none() { return; } //init_noop
+        self.surface = None;
+        self.surface_size = None;
+        self.msaa = None;
+        self.depth = None;
+        self.suspended = true;
+    }
+
+    //new surface for the same window, the format and present modes chosen at init are kept
+    pub(crate) fn resume(&mut self, window: Arc<Window>) -> Result<()>
+    {
+        if !self.suspended { return Ok(()); }
+        let size = window.inner_size().into();
+        self.surface = Some(self.instance.create_surface(window)?);
+        self.suspended = false;
+        self.configure(size);
+        Ok(())
+    }
+
+    pub fn is_suspended(&self) -> bool { self.suspended }
 
-        Ok(Self { instance, backend, surface, surface_format, surface_size, view_format, device, queue })
+    fn watch_device_lost(device: &wgpu::Device) -> Arc<AtomicBool>
+    {
+        let lost = Arc::new(AtomicBool::new(false));
+        let flag = lost.clone();
+        device.set_device_lost_callback(move |reason, message|
+        {
+            //Destroyed is our own drop, e.g. when graphics is replaced
+            if reason == wgpu::DeviceLostReason::Destroyed { return; }
+            log::error!("device lost: {message}");
+            flag.store(true, Ordering::Relaxed);
+        });
+        lost
+    }
+
+    fn trace(config: &Config) -> wgpu::Trace
+    {
+        #[cfg(all(feature = "trace", not(target_arch = "wasm32")))]
+        if let Some(path) = &config.trace_path
+        {
+            if let Err(err) = std::fs::create_dir_all(path) { log::warn!("wgpu trace directory: {err}"); }
+            return wgpu::Trace::Directory(path.clone());
+        }
+        #[cfg(not(all(feature = "trace", not(target_arch = "wasm32"))))]
+        if config.trace_path.is_some() { log::warn!("wgpu trace requested without the trace feature"); }
+        wgpu::Trace::Off
+    }
+
+    //the auto modes are always supported
+    fn supported_present_mode(modes: &[wgpu::PresentMode], mode: wgpu::PresentMode) -> wgpu::PresentMode
+    {
+        use wgpu::PresentMode::*;
+        if matches!(mode, AutoVsync | AutoNoVsync) || modes.contains(&mode) { return mode; }
+        let fallback = if matches!(mode, Immediate | Mailbox) { AutoNoVsync } else { AutoVsync };
+        log::warn!("present mode {mode:?} unsupported, using {fallback:?}");
+        fallback
+    }
+
+    //tearing is allowed outside the vrr window, frames are paced to the refresh rate by the frame loop
+    fn vrr_present_mode(modes: &[wgpu::PresentMode]) -> wgpu::PresentMode
+    {
+        use wgpu::PresentMode::*;
+        [Immediate, FifoRelaxed].into_iter().find(|mode| modes.contains(mode)).unwrap_or_else(||
+        {
+            log::warn!("no vrr present mode supported, using AutoVsync");
+            AutoVsync
+        })
     }
 
     pub(crate) fn configure(&mut self, (width, height): (u32, u32))
     {
-        if width > 0 && height > 0 && Some((width, height)) != self.surface_size
+        if !self.suspended && width > 0 && height > 0 && Some((width, height)) != self.surface_size
         {
             self.surface_size = Some((width, height));
-            let surface_conf = wgpu::SurfaceConfiguration
-            {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: self.surface_format,
-                width, height,
-                present_mode: wgpu::PresentMode::AutoVsync,
-                desired_maximum_frame_latency: 2,
-                alpha_mode: wgpu::CompositeAlphaMode::Auto,
-                view_formats: if self.surface_format == self.view_format { vec![] } else { vec![self.view_format] },
-            };
-            self.surface.configure(&self.device, &surface_conf);
+            self.reconfigure();
         }
     }
 
+    fn reconfigure(&mut self)
+    {
+        let Some((width, height)) = self.surface_size else { return; };
+        let surface_conf = wgpu::SurfaceConfiguration
+        {
+            usage: self.surface_usage,
+            format: self.surface_format,
+            width, height,
+            present_mode: self.present_mode,
+            desired_maximum_frame_latency: self.frame_latency(),
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: if self.surface_format == self.view_format { vec![] } else { vec![self.view_format] },
+        };
+        match &self.surface
+        {
+            Some(surface) => surface.configure(&self.device, &surface_conf),
+            None => self.dummy = Some(msaa::create_texture(self, self.view_format, 1, (width, height), self.surface_usage)),
+        }
+        self.msaa = (self.sample_count > 1).then(|| msaa::create_texture(self, self.view_format, self.sample_count, (width, height), wgpu::TextureUsages::RENDER_ATTACHMENT));
+        self.depth = match self.depth.take()
+        {
+            Some(mut depth) =>
+            {
+                depth.update(self);
+                Some(depth)
+            },
+            None => self.config.depth.map(|config| DepthBuffer::new(self, config)),
+        };
+    }
+
+    pub fn present_mode(&self) -> wgpu::PresentMode { self.present_mode }
+    pub fn present_modes(&self) -> &[wgpu::PresentMode] { &self.present_modes }
+    //Config::vrr got a vrr present mode, whether the display actually runs variable refresh can not be queried
+    pub fn is_vrr(&self) -> bool { self.config.vrr && matches!(self.present_mode, wgpu::PresentMode::Immediate | wgpu::PresentMode::FifoRelaxed) }
+
+    //reconfigures the surface, e.g. for a vsync toggle
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode)
+    {
+        let mode = Self::supported_present_mode(&self.present_modes, mode);
+        if mode == self.present_mode { return; }
+        self.present_mode = mode;
+        self.reconfigure();
+    }
+
+    pub fn config(&self) -> &Config { &self.config }
+    //the gpu was reset, the frame loop recreates graphics on its own
+    pub fn is_device_lost(&self) -> bool { self.lost.load(Ordering::Relaxed) }
+    //name, backend, device type and driver, e.g. for an about dialog
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo { self.adapter.get_info() }
     pub fn backend(&self) -> wgpu::Backend { self.backend }
     pub fn view_format(&self) -> wgpu::TextureFormat { self.view_format }
+    //false with App::SRGB_VIEW off on a linear surface, shaders then encode srgb themselves like the ui does
+    pub fn is_srgb_view(&self) -> bool { self.view_format.is_srgb() }
     pub fn surface_size(&self) -> Option<(u32, u32)> { self.surface_size }
+    pub fn surface_format(&self) -> wgpu::TextureFormat { self.surface_format }
+    pub fn surface_usage(&self) -> wgpu::TextureUsages { self.surface_usage }
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+    //surface sized multisampled target for SAMPLE_COUNT > 1, resolve into the surface view
+    pub fn msaa_view(&self) -> Option<&wgpu::TextureView> { self.msaa.as_ref().map(|(_, view)| view) }
+    //None without Config::depth, recreated on resize, its state() goes into app pipelines drawing with Pass::frame
+    pub fn depth(&self) -> Option<&DepthBuffer> { self.depth.as_ref() }
+    pub fn depth_texture(&self) -> Option<&wgpu::Texture> { self.depth.as_ref()?.texture() }
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> { self.depth.as_ref()?.view() }
+
+    //view in the raw surface format, requires STORAGE_BINDING in Config::surface_usage
+    pub fn storage_view(&self, texture: &wgpu::SurfaceTexture) -> wgpu::TextureView
+    {
+        let view_descr = wgpu::TextureViewDescriptor
+        {
+            label: None,
+            format: Some(self.surface_format),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            usage: Some(wgpu::TextureUsages::STORAGE_BINDING),
+            aspect: wgpu::TextureAspect::All,
+            base_mip_level: 0,
+            mip_level_count: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+        };
+        texture.texture.create_view(&view_descr)
+    }
 
-    pub fn current_surface(&mut self) -> Result<Option<(wgpu::SurfaceTexture, wgpu::TextureView)>>
+    //the returned texture must be handed to present() after submitting its commands,
+    //an unpresented texture keeps its swapchain image and stalls the next acquire
+    pub fn current_surface(&mut self) -> Result<FrameAcquire>
     {
-        let Some(size) = self.surface_size else { return Ok(None); };
+        if self.surface_size.is_none() { return Ok(FrameAcquire::NotConfigured); }
+        let Some(surface) = &self.surface else { return Ok(FrameAcquire::Unavailable); };
 
-        let texture = match self.surface.get_current_texture()
+        let texture = match surface.get_current_texture()
         {
             wgpu::CurrentSurfaceTexture::Success(texture) => texture,
-            wgpu::CurrentSurfaceTexture::Suboptimal(_) =>
-            {
-                self.configure(size);
-                return Ok(None);
-            },
-            wgpu::CurrentSurfaceTexture::Timeout => return Ok(None),
-            wgpu::CurrentSurfaceTexture::Occluded => return Ok(None),
-            wgpu::CurrentSurfaceTexture::Outdated =>
+            wgpu::CurrentSurfaceTexture::Suboptimal(_) | wgpu::CurrentSurfaceTexture::Outdated =>
             {
-                self.configure(size);
-                return Ok(None);
+                self.reconfigure();
+                return Ok(FrameAcquire::Reconfigured);
             },
+            wgpu::CurrentSurfaceTexture::Timeout | wgpu::CurrentSurfaceTexture::Occluded => return Ok(FrameAcquire::Unavailable),
             wgpu::CurrentSurfaceTexture::Lost => return Err(Error::Surface(SurfaceError::Lost)),
             wgpu::CurrentSurfaceTexture::Validation => return Err(Error::Surface(SurfaceError::Validation)),
         };
@@ -148,7 +646,7 @@ impl Graphics
             label: None,
             format: Some(self.view_format),
             dimension: Some(wgpu::TextureViewDimension::D2),
-            usage: None,
+            usage: Some(self.surface_usage - wgpu::TextureUsages::STORAGE_BINDING),
             aspect: wgpu::TextureAspect::All,
             base_mip_level: 0,
             mip_level_count: None,
@@ -157,6 +655,145 @@ impl Graphics
         };
         let view = texture.texture.create_view(&view_descr);
 
-        Ok(Some((texture, view)))
+        Ok(FrameAcquire::Ready(texture, view))
+    }
+
+    pub fn present(&self, texture: wgpu::SurfaceTexture)
+    {
+        #[cfg(feature = "test-capture")]
+        crate::capture::record(|| crate::capture::Op::Present);
+        texture.present();
+        track_present(&self.queue, &self.in_flight);
+    }
+
+    fn frame_latency(&self) -> u32 { if self.is_vrr() { 1 } else { 2 } }
+
+    //format None uses view_format() so sampled results match the surface color space
+    pub fn create_render_target(&self, width: u32, height: u32, format: Option<wgpu::TextureFormat>, usage: wgpu::TextureUsages) -> RenderTarget
+    {
+        RenderTarget::new(self, (width, height), format.unwrap_or(self.view_format), usage)
+    }
+
+    pub fn create_encoder(&self, label: &str) -> wgpu::CommandEncoder
+    {
+        self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some(label) })
+    }
+
+    //groups commands in gpu captures (renderdoc, xcode), pops must match pushes
+    pub fn push_debug_group(&self, encoder: &mut wgpu::CommandEncoder, label: &str) { encoder.push_debug_group(label); }
+    pub fn pop_debug_group(&self, encoder: &mut wgpu::CommandEncoder) { encoder.pop_debug_group(); }
+
+    //None on backends without allocator reports (gl, webgpu)
+    pub fn memory_report(&self) -> Option<MemoryReport>
+    {
+        let report = self.device.generate_allocator_report()?;
+        let blocks = report.blocks.iter()
+            .map(|block| (block.size, report.allocations[block.allocations.clone()].iter().map(|allocation| allocation.size).sum()))
+            .collect();
+        Some(MemoryReport { allocated: report.total_allocated_bytes, capacity: report.total_reserved_bytes, blocks })
+    }
+
+    //validates bounds, alignment and usage instead of failing wgpu validation
+    pub fn write_buffer_checked(&self, buffer: &wgpu::Buffer, offset: u64, data: &[u8]) -> Result<()>
+    {
+        let (len, size) = (data.len() as u64, buffer.size());
+        if !buffer.usage().contains(wgpu::BufferUsages::COPY_DST) { return Err(Error::Buffer(BufferError::NotCopyDst)); }
+        if offset % wgpu::COPY_BUFFER_ALIGNMENT != 0 || len % wgpu::COPY_BUFFER_ALIGNMENT != 0 { return Err(Error::Buffer(BufferError::Unaligned { offset, len })); }
+        if offset.checked_add(len).is_none_or(|end| end > size) { return Err(Error::Buffer(BufferError::OutOfBounds { offset, len, size })); }
+        self.queue.write_buffer(buffer, offset, data);
+        Ok(())
+    }
+
+    //like current_surface, but presents automatically when the frame is dropped
+    pub fn frame(&mut self) -> Result<Option<Frame>>
+    {
+        Ok(self.acquire_frame()?.ok())
     }
+
+    //like acquire_frame, but returns FrameAcquire::Busy instead of blocking on vsync, for reactive apps repainting occasionally,
+    //wgpu has no non-blocking acquire so this estimates from the presented frames the gpu has not finished yet
+    pub fn try_acquire_frame(&mut self) -> Result<std::result::Result<Frame, FrameAcquire>>
+    {
+        if self.surface.is_some()
+        {
+            self.device.poll(wgpu::PollType::Poll)?;
+            if self.in_flight.load(Ordering::Relaxed) >= self.frame_latency() { return Ok(Err(FrameAcquire::Busy)); }
+        }
+        self.acquire_frame()
+    }
+
+    //keeps the reason for a skipped frame
+    pub fn acquire_frame(&mut self) -> Result<std::result::Result<Frame, FrameAcquire>>
+    {
+        if self.surface.is_none() && let Some((target, view)) = &self.dummy
+        {
+            let frame = Frame { texture: None, target: target.clone(), view: view.clone(), msaa_view: None, depth_view: self.depth_view().cloned(), overlay: None, in_flight: None };
+            return Ok(Ok(frame));
+        }
+        Ok(match self.current_surface()?
+        {
+            FrameAcquire::Ready(texture, view) => Ok(self.wrap_frame(texture, view)),
+            acquire => Err(acquire),
+        })
+    }
+
+    fn wrap_frame(&self, texture: wgpu::SurfaceTexture, view: wgpu::TextureView) -> Frame
+    {
+        Frame { target: texture.texture.clone(), texture: Some(texture), view, msaa_view: self.msaa_view().cloned(), depth_view: self.depth_view().cloned(), overlay: self.overlay.clone(), in_flight: Some((self.queue.clone(), self.in_flight.clone())) }
+    }
+
+    pub(crate) fn update_debug_overlay(&self, dt: f32)
+    {
+        let Some(overlay) = &self.overlay else { return; };
+        let mut info = format!("{:?}\n{}", self.backend, self.adapter.get_info().name);
+        if let Some(report) = self.memory_report() { info += &format!("\n{:.1} MB", report.allocated as f64 / 1e6); }
+        overlay.lock().unwrap().update(dt, &info);
+    }
+
+    //scissor region of the debug overlay drawn by Frame, None fits the text top left
+    pub fn set_debug_overlay_region(&self, region: Option<(u32, u32, u32, u32)>)
+    {
+        if let Some(overlay) = &self.overlay { overlay.lock().unwrap().set_region(region); }
+    }
+}
+
+//submit all commands rendering to view before dropping
+pub struct Frame
+{
+    texture: Option<wgpu::SurfaceTexture>, //Option for moving out in drop, None for init_noop
+    target: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub msaa_view: Option<wgpu::TextureView>, //render here with view as resolve target if SAMPLE_COUNT > 1, see Pass::frame
+    pub depth_view: Option<wgpu::TextureView>, //Graphics::depth_view
+    overlay: Option<debug_text::SharedOverlay>,
+    in_flight: Option<(wgpu::Queue, Arc<AtomicU32>)>,
+}
+
+impl Frame
+{
+    pub fn texture(&self) -> &wgpu::Texture { &self.target }
+    pub fn present(self) {}
+}
+
+impl Drop for Frame
+{
+    fn drop(&mut self)
+    {
+        if let Some(texture) = self.texture.take()
+        {
+            if let Some(overlay) = &self.overlay { overlay.lock().unwrap().render(&texture.texture, &self.view); }
+            #[cfg(feature = "test-capture")]
+            crate::capture::record(|| crate::capture::Op::Present);
+            texture.present();
+        }
+        if let Some((queue, in_flight)) = &self.in_flight { track_present(queue, in_flight); }
+    }
+}
+
+//counted until the gpu finishes the submitted work, see Graphics::try_acquire_frame
+fn track_present(queue: &wgpu::Queue, in_flight: &Arc<AtomicU32>)
+{
+    in_flight.fetch_add(1, Ordering::Relaxed);
+    let in_flight = in_flight.clone();
+    queue.on_submitted_work_done(move || { in_flight.fetch_sub(1, Ordering::Relaxed); });
 }
@@ -0,0 +1,165 @@
+use super::Graphics;
+
+const SHADER: wgpu::ShaderModuleDescriptor<'static> = wgpu::include_wgsl!("../blit.wgsl");
+
+//copies a texture onto a render target with a fullscreen triangle
+pub struct Blitter
+{
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+}
+
+impl Blitter
+{
+    pub fn new(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode) -> Self
+    {
+        Self::with_culling(graphics, target_format, filter, Some(wgpu::Face::Back))
+    }
+
+    //the triangle faces front with FRONT_FACE, culling Front renders nothing
+    pub fn with_culling(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode, cull_mode: Option<wgpu::Face>) -> Self
+    {
+        Self::with_options(graphics, target_format, filter, cull_mode, graphics.sample_count())
+    }
+
+    //sample_count has to match the pass, graphics.sample_count() for Pass::frame, 1 for single sampled targets
+    pub fn with_options(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode, cull_mode: Option<wgpu::Face>, sample_count: u32) -> Self
+    {
+        let device = &graphics.device;
+        let bind_group_layout_descr = wgpu::BindGroupLayoutDescriptor
+        {
+            label: None,
+            entries:
+            &[
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture
+                    {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        };
+        let bind_group_layout = device.create_bind_group_layout(&bind_group_layout_descr);
+
+        let pipeline_layout_descr = wgpu::PipelineLayoutDescriptor
+        {
+            label: None,
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        };
+        let pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descr);
+
+        let shader = device.create_shader_module(SHADER);
+        let color_target_state = Some(wgpu::ColorTargetState
+        {
+            format: target_format,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        });
+        let render_pipeline_descr = wgpu::RenderPipelineDescriptor
+        {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState
+            {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState
+            {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: super::FRONT_FACE,
+                cull_mode,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState
+            {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState
+            {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: std::slice::from_ref(&color_target_state),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview_mask: None,
+            cache: None,
+        };
+        let render_pipeline = device.create_render_pipeline(&render_pipeline_descr);
+
+        let sampler_descr = wgpu::SamplerDescriptor
+        {
+            label: None,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::MipmapFilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        };
+        let sampler = device.create_sampler(&sampler_descr);
+
+        Self { bind_group_layout, render_pipeline, sampler }
+    }
+
+    pub fn bind_group(&self, graphics: &Graphics, source: &wgpu::TextureView) -> wgpu::BindGroup
+    {
+        let bind_group_descr = wgpu::BindGroupDescriptor
+        {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries:
+            &[
+                wgpu::BindGroupEntry
+                {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry
+                {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ]
+        };
+        graphics.device.create_bind_group(&bind_group_descr)
+    }
+
+    //draws into the pass, restrict to a region with render_pass.set_viewport beforehand
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass, bind_group: &wgpu::BindGroup)
+    {
+        #[cfg(feature = "test-capture")]
+        crate::capture::record(|| crate::capture::Op::Blit);
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,23 @@
+use gru_misc::math::Vec2;
+
+//screen: pixels, origin top left, y down
+//ndc: wgpu clip space, origin center, y up
+
+//vulkan style y down <-> wgpu y up
+pub fn flip_y(v: Vec2) -> Vec2 { v.component_mul(Vec2(1.0, -1.0)) }
+
+pub fn screen_to_ndc(pos: Vec2, size: Vec2) -> Vec2
+{
+    flip_y(pos.component_div(size) * 2.0 - Vec2(1.0, 1.0))
+}
+
+pub fn ndc_to_screen(ndc: Vec2, size: Vec2) -> Vec2
+{
+    (flip_y(ndc) + Vec2(1.0, 1.0)).component_mul(size) * 0.5
+}
+
+//for extents, without the origin shift
+pub fn screen_to_ndc_size(extent: Vec2, size: Vec2) -> Vec2
+{
+    flip_y(extent.component_div(size) * 2.0)
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use gru_misc::math::{Vec2, Vec4};
+use super::{Graphics, RectRenderer};
+
+//3x5 pixel glyphs, rows top to bottom, msb left
+const FONT: &[(char, u16)] =
+&[
+    ('0', 0b111_101_101_101_111), ('1', 0b010_110_010_010_111), ('2', 0b111_001_111_100_111), ('3', 0b111_001_111_001_111),
+    ('4', 0b101_101_111_001_001), ('5', 0b111_100_111_001_111), ('6', 0b111_100_111_101_111), ('7', 0b111_001_001_001_001),
+    ('8', 0b111_101_111_101_111), ('9', 0b111_101_111_001_111), ('A', 0b010_101_111_101_101), ('B', 0b110_101_110_101_110),
+    ('C', 0b011_100_100_100_011), ('D', 0b110_101_101_101_110), ('E', 0b111_100_110_100_111), ('F', 0b111_100_110_100_100),
+    ('G', 0b011_100_101_101_011), ('H', 0b101_101_111_101_101), ('I', 0b111_010_010_010_111), ('J', 0b001_001_001_101_010),
+    ('K', 0b101_101_110_101_101), ('L', 0b100_100_100_100_111), ('M', 0b101_111_111_101_101), ('N', 0b110_101_101_101_101),
+    ('O', 0b010_101_101_101_010), ('P', 0b110_101_110_100_100), ('Q', 0b010_101_101_110_011), ('R', 0b110_101_110_101_101),
+    ('S', 0b011_100_010_001_110), ('T', 0b111_010_010_010_010), ('U', 0b101_101_101_101_111), ('V', 0b101_101_101_101_010),
+    ('W', 0b101_101_111_111_101), ('X', 0b101_101_010_101_101), ('Y', 0b101_101_010_010_010), ('Z', 0b111_001_010_100_111),
+    ('.', 0b000_000_000_000_010), (':', 0b000_010_000_010_000), ('-', 0b000_000_111_000_000), ('/', 0b001_001_010_100_100),
+    ('%', 0b101_001_010_100_101), ('(', 0b001_010_010_010_001), (')', 0b100_010_010_010_100), (' ', 0),
+];
+const UNKNOWN: u16 = 0b111_001_010_000_010; //?
+const ADVANCE: f32 = 4.0;
+const LINE_HEIGHT: f32 = 6.0;
+
+//minimal uppercase pixel font on top of RectRenderer, for diagnostics without the ui feature
+pub struct DebugText
+{
+    rects: RectRenderer,
+}
+
+impl DebugText
+{
+    pub fn new(graphics: &Graphics, target_format: wgpu::TextureFormat) -> Self
+    {
+        Self::with_sample_count(graphics, target_format, graphics.sample_count())
+    }
+
+    //see SpriteBatch::with_options
+    pub fn with_sample_count(graphics: &Graphics, target_format: wgpu::TextureFormat, sample_count: u32) -> Self
+    {
+        Self { rects: RectRenderer::with_sample_count(graphics, target_format, sample_count) }
+    }
+
+    //pixel size of the text, lower case is drawn upper case
+    pub fn text_size(text: &str, scale: f32) -> Vec2
+    {
+        let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        let rows = text.lines().count();
+        Vec2((columns as f32 * ADVANCE - 1.0).max(0.0) * scale, (rows as f32 * LINE_HEIGHT - 1.0).max(0.0) * scale)
+    }
+
+    pub fn begin(&mut self, target_size: (u32, u32)) { self.rects.begin(target_size); }
+
+    pub fn rect(&mut self, position: Vec2, size: Vec2, color: Vec4) { self.rects.draw_rect(position, size, color); }
+
+    pub fn text(&mut self, position: Vec2, scale: f32, color: Vec4, text: &str)
+    {
+        for (row, line) in text.lines().enumerate()
+        {
+            for (column, c) in line.chars().enumerate()
+            {
+                let c = c.to_ascii_uppercase();
+                let glyph = FONT.iter().find(|(g, _)| *g == c).map_or(UNKNOWN, |(_, bits)| *bits);
+                let origin = position + Vec2(column as f32 * ADVANCE, row as f32 * LINE_HEIGHT) * scale;
+                for bit in 0..15
+                {
+                    if glyph & (1 << (14 - bit)) == 0 { continue; }
+                    let pixel = Vec2((bit % 3) as f32, (bit / 3) as f32) * scale;
+                    self.rect(origin + pixel, Vec2(scale, scale), color);
+                }
+            }
+        }
+    }
+
+    pub fn flush(&mut self, graphics: &Graphics, render_pass: &mut wgpu::RenderPass)
+    {
+        self.flush_raw(&graphics.device, &graphics.queue, render_pass);
+    }
+
+    fn flush_raw(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, render_pass: &mut wgpu::RenderPass)
+    {
+        self.rects.flush_raw(device, queue, render_pass);
+    }
+}
+
+//rendered by Frame right before presenting
+pub(crate) struct DebugOverlay
+{
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    text: DebugText,
+    lines: String,
+    region: Option<(u32, u32, u32, u32)>, //x, y, width, height, None fits the text top left
+    avg_dt: Option<f32>,
+}
+
+pub(crate) type SharedOverlay = Arc<Mutex<DebugOverlay>>; //a Mutex keeps Graphics and Frame Send + Sync
+
+impl DebugOverlay
+{
+    const SCALE: f32 = 2.0;
+    const PADDING: f32 = 4.0;
+
+    pub(crate) fn new(graphics: &Graphics) -> SharedOverlay
+    {
+        let text = DebugText::with_sample_count(graphics, graphics.view_format(), 1); //drawn onto the resolved surface
+        Arc::new(Mutex::new(Self { device: graphics.device.clone(), queue: graphics.queue.clone(), text, lines: String::new(), region: None, avg_dt: None }))
+    }
+
+    pub(crate) fn set_region(&mut self, region: Option<(u32, u32, u32, u32)>) { self.region = region; }
+
+    pub(crate) fn update(&mut self, dt: f32, info: &str)
+    {
+        let avg_dt = self.avg_dt.map_or(dt, |avg| avg + 0.05 * (dt - avg));
+        self.avg_dt = Some(avg_dt);
+        self.lines = format!("{:.0} FPS {:.2} MS\n{info}", 1.0 / avg_dt.max(1e-6), avg_dt * 1e3);
+    }
+
+    pub(crate) fn render(&mut self, texture: &wgpu::Texture, view: &wgpu::TextureView)
+    {
+        let (width, height) = (texture.width(), texture.height());
+        let size = DebugText::text_size(&self.lines, Self::SCALE) + Vec2(2.0, 2.0) * Self::PADDING;
+        let (x, y, w, h) = self.region.unwrap_or((0, 0, size.0.ceil() as u32, size.1.ceil() as u32));
+        let (x, y) = (x.min(width - 1), y.min(height - 1));
+        let (w, h) = (w.min(width - x), h.min(height - y));
+        if w == 0 || h == 0 { return; }
+
+        let origin = Vec2(x as f32, y as f32);
+        self.text.begin((width, height));
+        self.text.rect(origin, size, Vec4(0.0, 0.0, 0.0, 0.6));
+        self.text.text(origin + Vec2(Self::PADDING, Self::PADDING), Self::SCALE, Vec4(1.0, 1.0, 1.0, 1.0), &self.lines);
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("gru debug overlay") });
+        {
+            let mut render_pass = super::Pass::new().label("gru debug overlay").color(view, None).begin(&mut encoder);
+            render_pass.set_scissor_rect(x, y, w, h);
+            self.text.flush_raw(&self.device, &self.queue, &mut render_pass);
+        }
+        self.queue.submit([encoder.finish()]);
+    }
+}
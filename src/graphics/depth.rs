@@ -0,0 +1,72 @@
+use super::{Graphics, msaa};
+
+//depth testing for app pipelines, e.g. LessEqual on the sprite layer for order independent 2d layering
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthConfig
+{
+    pub format: wgpu::TextureFormat,
+    pub compare: wgpu::CompareFunction,
+    pub write: bool,
+}
+
+impl DepthConfig
+{
+    pub const LAYERED_2D: Self = Self { format: wgpu::TextureFormat::Depth32Float, compare: wgpu::CompareFunction::LessEqual, write: true };
+
+    //for RenderPipelineDescriptor::depth_stencil
+    pub fn state(&self) -> wgpu::DepthStencilState
+    {
+        wgpu::DepthStencilState
+        {
+            format: self.format,
+            depth_write_enabled: Some(self.write),
+            depth_compare: Some(self.compare),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+//depth texture following the surface size with Graphics::sample_count samples, matching Pass::frame,
+//Graphics owns one for Config::depth
+pub struct DepthBuffer
+{
+    config: DepthConfig,
+    sample_count: u32,
+    size: Option<(u32, u32)>,
+    target: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl DepthBuffer
+{
+    pub fn new(graphics: &Graphics, config: DepthConfig) -> Self
+    {
+        Self::with_sample_count(graphics, config, graphics.sample_count())
+    }
+
+    //1 for single sampled surface sized targets, e.g. a GBuffer
+    pub fn with_sample_count(graphics: &Graphics, config: DepthConfig, sample_count: u32) -> Self
+    {
+        let mut depth = Self { config, sample_count, size: None, target: None };
+        depth.update(graphics);
+        depth
+    }
+
+    //recreates the texture if the surface size changed, call once per frame
+    pub fn update(&mut self, graphics: &Graphics) -> bool
+    {
+        let size = graphics.surface_size();
+        if size == self.size { return false; }
+        self.size = size;
+        self.target = size.map(|size| msaa::create_texture(graphics, self.config.format, self.sample_count, size, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING));
+        true
+    }
+
+    pub fn config(&self) -> DepthConfig { self.config }
+    pub fn state(&self) -> wgpu::DepthStencilState { self.config.state() }
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+    pub fn size(&self) -> Option<(u32, u32)> { self.size }
+    pub fn texture(&self) -> Option<&wgpu::Texture> { self.target.as_ref().map(|(texture, _)| texture) }
+    //for Pass::depth
+    pub fn view(&self) -> Option<&wgpu::TextureView> { self.target.as_ref().map(|(_, view)| view) }
+}
@@ -0,0 +1,40 @@
+use super::{Graphics, msaa};
+
+//color targets following the surface size
+pub struct GBuffer
+{
+    formats: Vec<wgpu::TextureFormat>,
+    size: Option<(u32, u32)>,
+    targets: Vec<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl GBuffer
+{
+    pub fn new(graphics: &Graphics, formats: &[wgpu::TextureFormat]) -> Self
+    {
+        let mut gbuffer = Self { formats: formats.to_vec(), size: None, targets: Vec::new() };
+        gbuffer.update(graphics);
+        gbuffer
+    }
+
+    //recreates the targets if the surface size changed, call once per frame
+    pub fn update(&mut self, graphics: &Graphics) -> bool
+    {
+        let size = graphics.surface_size();
+        if size == self.size { return false; }
+        self.size = size;
+        self.targets.clear();
+        if let Some(size) = size
+        {
+            let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+            self.targets.extend(self.formats.iter().map(|&format| msaa::create_texture(graphics, format, 1, size, usage)));
+        }
+        true
+    }
+
+    pub fn size(&self) -> Option<(u32, u32)> { self.size }
+    pub fn formats(&self) -> &[wgpu::TextureFormat] { &self.formats }
+    pub fn texture(&self, index: usize) -> &wgpu::Texture { &self.targets[index].0 }
+    pub fn view(&self, index: usize) -> &wgpu::TextureView { &self.targets[index].1 }
+    pub fn views(&self) -> impl Iterator<Item = &wgpu::TextureView> { self.targets.iter().map(|(_, view)| view) }
+}
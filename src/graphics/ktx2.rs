@@ -0,0 +1,71 @@
+use super::Graphics;
+use crate::{Error, Result};
+
+fn wgpu_format(format: ktx2::Format) -> Option<wgpu::TextureFormat>
+{
+    use wgpu::{TextureFormat as F, AstcBlock, AstcChannel};
+    let astc = |channel| F::Astc { block: AstcBlock::B4x4, channel };
+    Some(match format
+    {
+        ktx2::Format::R8G8B8A8_UNORM => F::Rgba8Unorm,
+        ktx2::Format::R8G8B8A8_SRGB => F::Rgba8UnormSrgb,
+        ktx2::Format::BC1_RGBA_UNORM_BLOCK => F::Bc1RgbaUnorm,
+        ktx2::Format::BC1_RGBA_SRGB_BLOCK => F::Bc1RgbaUnormSrgb,
+        ktx2::Format::BC3_UNORM_BLOCK => F::Bc3RgbaUnorm,
+        ktx2::Format::BC3_SRGB_BLOCK => F::Bc3RgbaUnormSrgb,
+        ktx2::Format::BC4_UNORM_BLOCK => F::Bc4RUnorm,
+        ktx2::Format::BC5_UNORM_BLOCK => F::Bc5RgUnorm,
+        ktx2::Format::BC6H_UFLOAT_BLOCK => F::Bc6hRgbUfloat,
+        ktx2::Format::BC7_UNORM_BLOCK => F::Bc7RgbaUnorm,
+        ktx2::Format::BC7_SRGB_BLOCK => F::Bc7RgbaUnormSrgb,
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => F::Etc2Rgb8Unorm,
+        ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK => F::Etc2Rgb8UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK => F::Etc2Rgba8Unorm,
+        ktx2::Format::ETC2_R8G8B8A8_SRGB_BLOCK => F::Etc2Rgba8UnormSrgb,
+        ktx2::Format::ASTC_4x4_UNORM_BLOCK => astc(AstcChannel::Unorm),
+        ktx2::Format::ASTC_4x4_SRGB_BLOCK => astc(AstcChannel::UnormSrgb),
+        _ => return None,
+    })
+}
+
+//uploads all mip levels as stored, supercompressed (basis, zstd) files are not supported
+pub fn load_ktx2(graphics: &Graphics, bytes: &[u8]) -> Result<(wgpu::Texture, wgpu::TextureView)>
+{
+    use wgpu::util::DeviceExt;
+    let reader = ktx2::Reader::new(bytes)?;
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() { return Err(Error::Loader("supercompressed ktx2 not supported")); }
+    let format = header.format.and_then(wgpu_format).ok_or(Error::Loader("ktx2 format not supported"))?;
+    let supported = graphics.device.features().contains(format.required_features())
+        && graphics.adapter.get_texture_format_features(format).allowed_usages.contains(wgpu::TextureUsages::TEXTURE_BINDING);
+    if !supported { return Err(Error::TextureFormat(format)); }
+
+    let (layers, faces) = (header.layer_count.max(1), header.face_count.max(1));
+    let texture_descr = wgpu::TextureDescriptor
+    {
+        label: None,
+        size: wgpu::Extent3d
+        {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            depth_or_array_layers: layers * faces,
+        },
+        mip_level_count: header.level_count.max(1),
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    };
+    let data: Vec<u8> = reader.levels().flat_map(|level| level.data.iter().copied()).collect();
+    let texture = graphics.device.create_texture_with_data(&graphics.queue, &texture_descr, wgpu::util::TextureDataOrder::MipMajor, &data);
+    let dimension = match (faces, layers)
+    {
+        (6, 1) => wgpu::TextureViewDimension::Cube,
+        (6, _) => wgpu::TextureViewDimension::CubeArray,
+        (_, 1) if header.layer_count == 0 => wgpu::TextureViewDimension::D2,
+        _ => wgpu::TextureViewDimension::D2Array,
+    };
+    let view = texture.create_view(&wgpu::TextureViewDescriptor { dimension: Some(dimension), ..Default::default() });
+    Ok((texture, view))
+}
@@ -0,0 +1,54 @@
+use super::Graphics;
+
+//multisampled color target with a single-sampled resolve texture,
+//render with Pass::msaa or set view() as attachment and resolve_view() as its resolve_target
+pub struct MsaaTarget
+{
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    size: (u32, u32),
+    msaa: (wgpu::Texture, wgpu::TextureView),
+    resolve: (wgpu::Texture, wgpu::TextureView),
+}
+
+pub(crate) fn create_texture(graphics: &Graphics, format: wgpu::TextureFormat, sample_count: u32, (width, height): (u32, u32), usage: wgpu::TextureUsages) -> (wgpu::Texture, wgpu::TextureView)
+{
+    let texture_descr = wgpu::TextureDescriptor
+    {
+        label: None,
+        size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    };
+    let texture = graphics.device.create_texture(&texture_descr);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+pub fn create_msaa_target(graphics: &Graphics, format: wgpu::TextureFormat, sample_count: u32, size: (u32, u32)) -> MsaaTarget
+{
+    let size = (size.0.max(1), size.1.max(1));
+    let msaa = create_texture(graphics, format, sample_count, size, wgpu::TextureUsages::RENDER_ATTACHMENT);
+    let resolve = create_texture(graphics, format, 1, size, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC);
+    MsaaTarget { format, sample_count, size, msaa, resolve }
+}
+
+impl MsaaTarget
+{
+    pub fn resize(&mut self, graphics: &Graphics, size: (u32, u32))
+    {
+        let size = (size.0.max(1), size.1.max(1)); //self.size is stored clamped
+        if size != self.size { *self = create_msaa_target(graphics, self.format, self.sample_count, size); }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+    pub fn size(&self) -> (u32, u32) { self.size }
+    pub fn view(&self) -> &wgpu::TextureView { &self.msaa.1 }
+    pub fn resolve_texture(&self) -> &wgpu::Texture { &self.resolve.0 }
+    pub fn resolve_view(&self) -> &wgpu::TextureView { &self.resolve.1 }
+}
@@ -0,0 +1,153 @@
+pub struct Pass<'a>
+{
+    label: Option<&'a str>,
+    colors: Vec<Option<wgpu::RenderPassColorAttachment<'a>>>,
+    depth: Option<wgpu::RenderPassDepthStencilAttachment<'a>>,
+}
+
+impl<'a> Pass<'a>
+{
+    pub fn new() -> Self
+    {
+        Self { label: None, colors: Vec::new(), depth: None }
+    }
+
+    pub fn label(mut self, label: &'a str) -> Self
+    {
+        self.label = Some(label);
+        self
+    }
+
+    //clear None loads the previous contents
+    pub fn color(self, view: &'a wgpu::TextureView, clear: Option<wgpu::Color>) -> Self
+    {
+        self.color_ops(view, attachment_ops(clear, true))
+    }
+
+    //full control, e.g. discard a color target only needed within this pass
+    pub fn color_ops(mut self, view: &'a wgpu::TextureView, ops: wgpu::Operations<wgpu::Color>) -> Self
+    {
+        let attachment = wgpu::RenderPassColorAttachment
+        {
+            view,
+            depth_slice: None,
+            resolve_target: None,
+            ops,
+        };
+        self.colors.push(Some(attachment));
+        self
+    }
+
+    //renders multisampled and resolves into resolve_view, the msaa contents are discarded
+    pub fn msaa(mut self, target: &'a super::MsaaTarget, clear: Option<wgpu::Color>) -> Self
+    {
+        let attachment = wgpu::RenderPassColorAttachment
+        {
+            view: target.view(),
+            depth_slice: None,
+            resolve_target: Some(target.resolve_view()),
+            ops: attachment_ops(clear, false),
+        };
+        self.colors.push(Some(attachment));
+        self
+    }
+
+    //the msaa view resolving into the surface if SAMPLE_COUNT > 1, else the surface view
+    pub fn frame(mut self, frame: &'a super::Frame, clear: Option<wgpu::Color>) -> Self
+    {
+        let Some(msaa_view) = &frame.msaa_view else { return self.color(&frame.view, clear); };
+        let attachment = wgpu::RenderPassColorAttachment
+        {
+            view: msaa_view,
+            depth_slice: None,
+            resolve_target: Some(&frame.view),
+            ops: attachment_ops(clear, false),
+        };
+        self.colors.push(Some(attachment));
+        self
+    }
+
+    //color and the depth if any, a color clear also clears depth to 1
+    pub fn target(self, target: &'a super::RenderTarget, clear: Option<wgpu::Color>) -> Self
+    {
+        let pass = self.color(target.view(), clear);
+        match target.depth_view()
+        {
+            Some(depth) => pass.depth(depth, clear.map(|_| 1.0)),
+            None => pass,
+        }
+    }
+
+    //multiple render targets, bound in order
+    pub fn colors(self, views: impl IntoIterator<Item = &'a wgpu::TextureView>, clear: Option<wgpu::Color>) -> Self
+    {
+        views.into_iter().fold(self, |pass, view| pass.color(view, clear))
+    }
+
+    //clear None loads the previous contents, depth is stored for later passes
+    pub fn depth(self, view: &'a wgpu::TextureView, clear: Option<f32>) -> Self
+    {
+        self.depth_stencil(view, Some(attachment_ops(clear, true)), None)
+    }
+
+    //e.g. a depth pre-pass stores, the following pass loads with store false to discard afterwards
+    pub fn depth_ops(self, view: &'a wgpu::TextureView, ops: wgpu::Operations<f32>) -> Self
+    {
+        self.depth_stencil(view, Some(ops), None)
+    }
+
+    //None leaves the aspect untouched (read only)
+    pub fn depth_stencil(mut self, view: &'a wgpu::TextureView, depth_ops: Option<wgpu::Operations<f32>>, stencil_ops: Option<wgpu::Operations<u32>>) -> Self
+    {
+        self.depth = Some(wgpu::RenderPassDepthStencilAttachment { view, depth_ops, stencil_ops });
+        self
+    }
+
+    pub fn begin<'e>(self, encoder: &'e mut wgpu::CommandEncoder) -> wgpu::RenderPass<'e>
+    {
+        #[cfg(feature = "test-capture")]
+        crate::capture::record(|| crate::capture::Op::Pass
+        {
+            label: self.label.map(str::to_string),
+            clears: self.colors.iter().flatten().map(|color| match color.ops.load
+            {
+                wgpu::LoadOp::Clear(c) => Some([c.r, c.g, c.b, c.a]),
+                _ => None,
+            }).collect(),
+            depth_clear: self.depth.as_ref().and_then(|depth| match depth.depth_ops?.load
+            {
+                wgpu::LoadOp::Clear(d) => Some(d),
+                _ => None,
+            }),
+        });
+        let pass_descr = wgpu::RenderPassDescriptor
+        {
+            label: self.label,
+            color_attachments: &self.colors,
+            depth_stencil_attachment: self.depth,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            multiview_mask: None,
+        };
+        encoder.begin_render_pass(&pass_descr)
+    }
+}
+
+//clear None loads, store false discards the contents after the pass
+pub fn attachment_ops<V>(clear: Option<V>, store: bool) -> wgpu::Operations<V>
+{
+    wgpu::Operations
+    {
+        load: match clear
+        {
+            Some(value) => wgpu::LoadOp::Clear(value),
+            None => wgpu::LoadOp::Load,
+        },
+        store: if store { wgpu::StoreOp::Store } else { wgpu::StoreOp::Discard },
+    }
+}
+
+impl Default for Pass<'_>
+{
+    fn default() -> Self { Self::new() }
+}
@@ -0,0 +1,74 @@
+use gru_misc::math::Vec2;
+use super::{Graphics, Blitter, Pass, msaa};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Letterbox
+{
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub scale: f32,
+}
+
+//fixed size render target, nearest integer-upscaled onto the surface with black bars
+pub struct RenderResolution
+{
+    size: (u32, u32),
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    blitter: Blitter,
+    bind_group: wgpu::BindGroup,
+}
+
+impl RenderResolution
+{
+    pub fn new(graphics: &Graphics, (width, height): (u32, u32)) -> Self
+    {
+        let size = (width.max(1), height.max(1));
+        let (texture, view) = msaa::create_texture(graphics, graphics.view_format(), 1, size, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING);
+        let blitter = Blitter::with_options(graphics, graphics.view_format(), wgpu::FilterMode::Nearest, Some(wgpu::Face::Back), 1); //present draws onto the surface view
+        let bind_group = blitter.bind_group(graphics, &view);
+        Self { size, texture, view, blitter, bind_group }
+    }
+
+    pub fn size(&self) -> (u32, u32) { self.size }
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+    pub fn view(&self) -> &wgpu::TextureView { &self.view }
+
+    //integer scale if the surface is large enough, fractional downscale otherwise
+    pub fn letterbox(&self, (surface_width, surface_height): (u32, u32)) -> Letterbox
+    {
+        let (width, height) = self.size;
+        let fit = (surface_width as f32 / width as f32).min(surface_height as f32 / height as f32);
+        let scale = if fit >= 1.0 { fit.floor() } else { fit };
+        let (box_width, box_height) = (width as f32 * scale, height as f32 * scale);
+        Letterbox
+        {
+            x: ((surface_width as f32 - box_width) / 2.0).floor(),
+            y: ((surface_height as f32 - box_height) / 2.0).floor(),
+            width: box_width,
+            height: box_height,
+            scale,
+        }
+    }
+
+    //maps a surface pixel position into the logical resolution, None in the bars
+    pub fn to_logical(&self, surface_size: (u32, u32), pos: Vec2) -> Option<Vec2>
+    {
+        let letterbox = self.letterbox(surface_size);
+        let logical = (pos - Vec2(letterbox.x, letterbox.y)) * (1.0 / letterbox.scale);
+        let inside = logical.0 >= 0.0 && logical.1 >= 0.0 && logical.0 < self.size.0 as f32 && logical.1 < self.size.1 as f32;
+        inside.then_some(logical)
+    }
+
+    //draws the logical target onto the surface view
+    pub fn present(&self, graphics: &Graphics, encoder: &mut wgpu::CommandEncoder, surface_view: &wgpu::TextureView)
+    {
+        let Some(surface_size) = graphics.surface_size() else { return; };
+        let letterbox = self.letterbox(surface_size);
+        let mut render_pass = Pass::new().color(surface_view, Some(wgpu::Color::BLACK)).begin(encoder);
+        render_pass.set_viewport(letterbox.x, letterbox.y, letterbox.width, letterbox.height, 0.0, 1.0);
+        self.blitter.draw(&mut render_pass, &self.bind_group);
+    }
+}
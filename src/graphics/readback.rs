@@ -0,0 +1,228 @@
+use std::{ops::Range, sync::{Arc, Mutex}, pin::Pin, task::{self, Poll, Waker}, future::Future, collections::VecDeque};
+use super::{Graphics, Frame, aligned_bytes_per_row};
+use crate::{Error, BufferError, Result};
+
+const FRAMES_IN_FLIGHT: usize = 3; //desired_maximum_frame_latency + 1
+
+type MapResult = std::result::Result<(), wgpu::BufferAsyncError>;
+
+#[derive(Default)]
+struct MapState
+{
+    result: Option<MapResult>,
+    waker: Option<Waker>,
+}
+
+struct MapFuture(Arc<Mutex<MapState>>);
+
+impl Future for MapFuture
+{
+    type Output = MapResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output>
+    {
+        let mut state = self.0.lock().unwrap();
+        match state.result.take()
+        {
+            Some(result) => Poll::Ready(result),
+            None =>
+            {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+enum Slot
+{
+    Free,
+    Recorded, //copy recorded, not yet submitted
+    Mapping(Arc<Mutex<Option<MapResult>>>),
+}
+
+//staging buffers for reading back data of earlier frames without stalling
+pub struct ReadbackRing
+{
+    size: u64,
+    buffers: Vec<(wgpu::Buffer, Slot)>,
+    queue: VecDeque<usize>, //in flight slots, oldest first
+}
+
+impl ReadbackRing
+{
+    pub fn new(graphics: &Graphics, size: u64, count: usize) -> Self
+    {
+        let size = size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+        let buffers = (0..count.max(1)).map(|_|
+        {
+            let staging_descr = wgpu::BufferDescriptor
+            {
+                label: Some("gru readback"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            };
+            (graphics.device.create_buffer(&staging_descr), Slot::Free)
+        }).collect();
+        Self { size, buffers, queue: VecDeque::new() }
+    }
+
+    pub fn size(&self) -> u64 { self.size }
+    pub fn count(&self) -> usize { self.buffers.len() }
+
+    //None while all buffers are in flight, the slot is readable after submitted() and a few frames
+    pub fn free_buffer(&mut self) -> Option<(usize, &wgpu::Buffer)>
+    {
+        let slot = self.buffers.iter().position(|(_, slot)| matches!(slot, Slot::Free))?;
+        self.buffers[slot].1 = Slot::Recorded;
+        self.queue.push_back(slot);
+        Some((slot, &self.buffers[slot].0))
+    }
+
+    //records a buffer copy into a free slot
+    pub fn copy_buffer(&mut self, encoder: &mut wgpu::CommandEncoder, source: &wgpu::Buffer, offset: u64) -> Option<usize>
+    {
+        let size = self.size;
+        let (slot, staging) = self.free_buffer()?;
+        encoder.copy_buffer_to_buffer(source, offset, staging, 0, size);
+        Some(slot)
+    }
+
+    //starts mapping the recorded slots, call after submitting their commands
+    pub fn submitted(&mut self)
+    {
+        for (buffer, slot) in &mut self.buffers
+        {
+            if !matches!(slot, Slot::Recorded) { continue; }
+            let result = Arc::new(Mutex::new(None));
+            let callback_result = result.clone();
+            buffer.slice(..).map_async(wgpu::MapMode::Read, move |res| *callback_result.lock().unwrap() = Some(res));
+            *slot = Slot::Mapping(result);
+        }
+    }
+
+    //oldest finished readback (slot, data), never blocks
+    pub fn poll(&mut self, graphics: &Graphics) -> Option<Result<(usize, Vec<u8>)>>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(err) = graphics.device.poll(wgpu::PollType::Poll) { return Some(Err(err.into())); }
+        #[cfg(target_arch = "wasm32")]
+        let _ = graphics;
+        let &slot = self.queue.front()?;
+        let (buffer, state) = &mut self.buffers[slot];
+        let Slot::Mapping(result) = state else { return None; };
+        let result = result.lock().unwrap().take()?;
+        self.queue.pop_front();
+        *state = Slot::Free;
+        if let Err(err) = result { return Some(Err(err.into())); }
+        let data = buffer.slice(..).get_mapped_range().to_vec();
+        buffer.unmap();
+        Some(Ok((slot, data)))
+    }
+}
+
+impl Graphics
+{
+    //one staging buffer per frame in flight
+    pub fn readback_ring(&self, size: u64) -> ReadbackRing
+    {
+        ReadbackRing::new(self, size, FRAMES_IN_FLIGHT)
+    }
+
+    //maps the slice for reading, blocks on native and yields to the browser on wasm
+    pub(crate) async fn map_read(&self, slice: wgpu::BufferSlice<'_>) -> Result<()>
+    {
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        slice.map_async(wgpu::MapMode::Read, move |result|
+        {
+            let mut state = callback_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() { waker.wake(); }
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        self.device.poll(wgpu::PollType::wait_indefinitely())?;
+        MapFuture(state).await?;
+        Ok(())
+    }
+
+    //buffer needs COPY_SRC usage, unaligned ranges are widened internally up to the buffer size,
+    //so the tail of a buffer with an unaligned size can not be read
+    pub async fn read_buffer(&self, buffer: &wgpu::Buffer, range: Range<u64>) -> Result<Vec<u8>>
+    {
+        let (offset, len, buffer_size) = (range.start, range.end.saturating_sub(range.start), buffer.size());
+        if range.start > range.end || range.end > buffer_size { return Err(Error::Buffer(BufferError::OutOfBounds { offset, len, size: buffer_size })); }
+        if len == 0 { return Ok(Vec::new()); }
+        let align = wgpu::COPY_BUFFER_ALIGNMENT;
+        let start = range.start - range.start % align;
+        let end = (range.end.div_ceil(align) * align).min(buffer_size);
+        let size = end - start;
+        if size % align != 0 { return Err(Error::Buffer(BufferError::Unaligned { offset, len })); }
+
+        let staging_descr = wgpu::BufferDescriptor
+        {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+        let staging = self.device.create_buffer(&staging_descr);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(buffer, start, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        self.map_read(slice).await?;
+        let offset = (range.start - start) as usize;
+        let data = slice.get_mapped_range()[offset..offset + (range.end - range.start) as usize].to_vec();
+        staging.unmap();
+        Ok(data)
+    }
+
+    //tightly packed rgba8 (bgra is swizzled) with width and height, texture needs COPY_SRC usage
+    pub async fn read_texture(&self, texture: &wgpu::Texture) -> Result<(Vec<u8>, u32, u32)>
+    {
+        use wgpu::TextureFormat::*;
+        let format = texture.format();
+        let bgra = match format
+        {
+            Rgba8Unorm | Rgba8UnormSrgb => false,
+            Bgra8Unorm | Bgra8UnormSrgb => true,
+            _ => return Err(Error::TextureFormat(format)),
+        };
+        let (width, height) = (texture.width(), texture.height());
+        let (padded, unpadded) = aligned_bytes_per_row(width, 4);
+
+        let staging_descr = wgpu::BufferDescriptor
+        {
+            label: Some("gru texture readback"),
+            size: padded as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+        let staging = self.device.create_buffer(&staging_descr);
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer
+        (
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo { buffer: &staging, layout: wgpu::TexelCopyBufferLayout { offset: 0, bytes_per_row: Some(padded), rows_per_image: Some(height) } },
+            wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        self.map_read(slice).await?;
+        let mut data = Vec::with_capacity((unpadded * height) as usize);
+        for row in slice.get_mapped_range().chunks_exact(padded as usize) { data.extend_from_slice(&row[..unpadded as usize]); }
+        staging.unmap();
+        if bgra { for pixel in data.chunks_exact_mut(4) { pixel.swap(0, 2); } }
+        Ok((data, width, height))
+    }
+
+    //screenshot of the frame before it is presented, needs COPY_SRC in App::SURFACE_USAGE, submit its rendering first
+    pub async fn read_frame(&self, frame: &Frame) -> Result<(Vec<u8>, u32, u32)>
+    {
+        self.read_texture(frame.texture()).await
+    }
+}
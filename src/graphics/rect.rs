@@ -0,0 +1,69 @@
+use gru_misc::math::{Vec2, Vec4};
+use super::{Graphics, Sprite, SpriteBatch};
+
+//solid colored rectangles in pixel coordinates, a SpriteBatch over a white texture
+pub struct RectRenderer
+{
+    batch: SpriteBatch,
+    _texture: wgpu::Texture,
+    white: wgpu::BindGroup,
+}
+
+impl RectRenderer
+{
+    pub fn new(graphics: &Graphics, target_format: wgpu::TextureFormat) -> Self
+    {
+        Self::with_sample_count(graphics, target_format, graphics.sample_count())
+    }
+
+    //see SpriteBatch::with_options
+    pub fn with_sample_count(graphics: &Graphics, target_format: wgpu::TextureFormat, sample_count: u32) -> Self
+    {
+        use wgpu::util::DeviceExt;
+        let batch = SpriteBatch::with_options(graphics, target_format, wgpu::FilterMode::Nearest, None, super::AlphaMode::Straight, sample_count);
+        let layers = if graphics.backend() == wgpu::Backend::Gl { 2 } else { 1 }; //GL does not like TextureArray with 1 element
+        let texture_descr = wgpu::TextureDescriptor
+        {
+            label: None,
+            size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: layers },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        };
+        let texture = graphics.device.create_texture_with_data(&graphics.queue, &texture_descr, wgpu::util::TextureDataOrder::LayerMajor, &vec![255; 4 * layers as usize]);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor { dimension: Some(wgpu::TextureViewDimension::D2Array), ..Default::default() });
+        let white = batch.bind_group(graphics, &view);
+        Self { batch, _texture: texture, white }
+    }
+
+    pub fn begin(&mut self, target_size: (u32, u32)) { self.batch.begin(target_size); }
+
+    //top left position, drawn in call order
+    pub fn draw_rect(&mut self, position: Vec2, size: Vec2, color: Vec4)
+    {
+        self.batch.draw(0, Sprite { position, size, uv: (Vec2(0.0, 0.0), Vec2(1.0, 1.0)), color, layer: 0 });
+    }
+
+    //outline of width thickness inside the rectangle
+    pub fn draw_frame(&mut self, position: Vec2, size: Vec2, thickness: f32, color: Vec4)
+    {
+        let t = thickness.min(size.0 * 0.5).min(size.1 * 0.5);
+        self.draw_rect(position, Vec2(size.0, t), color);
+        self.draw_rect(position + Vec2(0.0, size.1 - t), Vec2(size.0, t), color);
+        self.draw_rect(position + Vec2(0.0, t), Vec2(t, size.1 - 2.0 * t), color);
+        self.draw_rect(position + Vec2(size.0 - t, t), Vec2(t, size.1 - 2.0 * t), color);
+    }
+
+    pub fn flush(&mut self, graphics: &Graphics, render_pass: &mut wgpu::RenderPass)
+    {
+        self.flush_raw(&graphics.device, &graphics.queue, render_pass);
+    }
+
+    pub(crate) fn flush_raw(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, render_pass: &mut wgpu::RenderPass)
+    {
+        self.batch.flush_raw(device, queue, render_pass, &[&self.white]);
+    }
+}
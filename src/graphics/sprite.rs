@@ -0,0 +1,272 @@
+use gru_misc::math::{Vec2, Vec4};
+use super::{Graphics, coords};
+
+const SHADER: wgpu::ShaderModuleDescriptor<'static> = wgpu::include_wgsl!("../sprite.wgsl");
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite
+{
+    pub position: Vec2, //top left in pixels
+    pub size: Vec2, //pixels, negative flips
+    pub uv: (Vec2, Vec2), //top left, bottom right
+    pub color: Vec4, //linear, multiplied with the texture
+    pub layer: u32, //array layer of the atlas
+}
+
+#[repr(C, packed)]
+struct Instance
+{
+    position: Vec2,
+    size: Vec2,
+    uv: [f32; 4],
+    color: Vec4,
+    layer: u32,
+}
+
+//instanced textured quads, one draw call per atlas
+pub struct SpriteBatch
+{
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    instance_buf: wgpu::Buffer,
+    len_instances: u64, //Instance count
+    target_size: Vec2,
+    sprites: Vec<(usize, Sprite)>,
+}
+
+impl SpriteBatch
+{
+    fn create_buffer(device: &wgpu::Device, len_instances: u64) -> wgpu::Buffer
+    {
+        let instance_buf_descr = wgpu::BufferDescriptor
+        {
+            label: Some("gru sprites"),
+            size: len_instances * std::mem::size_of::<Instance>() as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        };
+        device.create_buffer(&instance_buf_descr)
+    }
+
+    pub fn new(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode) -> Self
+    {
+        Self::with_culling(graphics, target_format, filter, None)
+    }
+
+    //quads with positive sizes face front with FRONT_FACE, negative sizes flip one axis and the winding
+    pub fn with_culling(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode, cull_mode: Option<wgpu::Face>) -> Self
+    {
+        Self::with_options(graphics, target_format, filter, cull_mode, super::AlphaMode::Straight, graphics.sample_count())
+    }
+
+    //Premultiplied expects premultiplied atlases and sprite colors,
+    //sample_count has to match the pass, graphics.sample_count() for Pass::frame, 1 for single sampled targets
+    pub fn with_options(graphics: &Graphics, target_format: wgpu::TextureFormat, filter: wgpu::FilterMode, cull_mode: Option<wgpu::Face>, alpha_mode: super::AlphaMode, sample_count: u32) -> Self
+    {
+        let device = &graphics.device;
+        let bind_group_layout_descr = wgpu::BindGroupLayoutDescriptor
+        {
+            label: None,
+            entries:
+            &[
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture
+                    {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry
+                {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ]
+        };
+        let bind_group_layout = device.create_bind_group_layout(&bind_group_layout_descr);
+
+        let pipeline_layout_descr = wgpu::PipelineLayoutDescriptor
+        {
+            label: None,
+            bind_group_layouts: &[Some(&bind_group_layout)],
+            immediate_size: 0,
+        };
+        let pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descr);
+
+        let shader = device.create_shader_module(SHADER);
+        let color_target_state = Some(wgpu::ColorTargetState
+        {
+            format: target_format,
+            blend: Some(alpha_mode.blend_state()),
+            write_mask: wgpu::ColorWrites::ALL,
+        });
+        let render_pipeline_descr = wgpu::RenderPipelineDescriptor
+        {
+            label: Some("gru sprites"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState
+            {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout
+                {
+                    array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4, 3 => Float32x4, 4 => Uint32]
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            primitive: wgpu::PrimitiveState
+            {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: super::FRONT_FACE,
+                cull_mode,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState
+            {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState
+            {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: std::slice::from_ref(&color_target_state),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            multiview_mask: None,
+            cache: None,
+        };
+        let render_pipeline = device.create_render_pipeline(&render_pipeline_descr);
+
+        let sampler_descr = wgpu::SamplerDescriptor
+        {
+            label: None,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::MipmapFilterMode::Nearest,
+            lod_min_clamp: 0.0,
+            lod_max_clamp: 32.0,
+            compare: None,
+            anisotropy_clamp: 1,
+            border_color: None,
+        };
+        let sampler = device.create_sampler(&sampler_descr);
+
+        let len_instances = 1;
+        let instance_buf = Self::create_buffer(device, len_instances);
+
+        Self { bind_group_layout, render_pipeline, sampler, instance_buf, len_instances, target_size: Vec2(1.0, 1.0), sprites: Vec::new() }
+    }
+
+    //atlas must be a D2Array view, e.g. AnimatedTexture::view
+    pub fn bind_group(&self, graphics: &Graphics, atlas: &wgpu::TextureView) -> wgpu::BindGroup
+    {
+        let bind_group_descr = wgpu::BindGroupDescriptor
+        {
+            label: None,
+            layout: &self.bind_group_layout,
+            entries:
+            &[
+                wgpu::BindGroupEntry
+                {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(atlas),
+                },
+                wgpu::BindGroupEntry
+                {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ]
+        };
+        graphics.device.create_bind_group(&bind_group_descr)
+    }
+
+    //starts a new batch for a render target of target_size pixels
+    pub fn begin(&mut self, (width, height): (u32, u32))
+    {
+        self.target_size = Vec2(width.max(1) as f32, height.max(1) as f32);
+        self.sprites.clear();
+    }
+
+    //atlas indexes the bind groups handed to flush
+    pub fn draw(&mut self, atlas: usize, sprite: Sprite)
+    {
+        self.sprites.push((atlas, sprite));
+    }
+
+    //uploads and draws all sprites in draw order per atlas,
+    //the upload happens on the next submit, so flush at most once per submit
+    pub fn flush(&mut self, graphics: &Graphics, render_pass: &mut wgpu::RenderPass, atlases: &[&wgpu::BindGroup])
+    {
+        self.flush_raw(&graphics.device, &graphics.queue, render_pass, atlases);
+    }
+
+    pub(crate) fn flush_raw(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, render_pass: &mut wgpu::RenderPass, atlases: &[&wgpu::BindGroup])
+    {
+        if self.sprites.is_empty() { return; }
+        self.sprites.sort_by_key(|(atlas, _)| *atlas);
+        //convert sprites
+        let instances: Vec<_> = self.sprites.iter().map(|(_, sprite)| Instance
+        {
+            position: coords::screen_to_ndc(sprite.position, self.target_size),
+            size: coords::screen_to_ndc_size(sprite.size, self.target_size),
+            uv: [sprite.uv.0.0, sprite.uv.0.1, sprite.uv.1.0, sprite.uv.1.1],
+            color: sprite.color,
+            layer: sprite.layer,
+        }).collect();
+        //create new buffer if too small
+        if instances.len() as u64 > self.len_instances
+        {
+            self.len_instances = (instances.len() as u64).next_power_of_two();
+            self.instance_buf = Self::create_buffer(device, self.len_instances);
+        }
+        let instance_bytes = unsafe
+        {
+            let ptr = instances.as_ptr() as *const u8;
+            std::slice::from_raw_parts(ptr, instances.len() * std::mem::size_of::<Instance>())
+        };
+        queue.write_buffer(&self.instance_buf, 0, instance_bytes); //Instance size is a multiple of 4
+        //one draw per atlas run
+        render_pass.push_debug_group("gru sprites");
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.instance_buf.slice(..));
+        let mut start = 0;
+        for run in self.sprites.chunk_by(|(a, _), (b, _)| a == b)
+        {
+            let end = start + run.len() as u32;
+            match atlases.get(run[0].0)
+            {
+                Some(bind_group) =>
+                {
+                    render_pass.set_bind_group(0, *bind_group, &[]);
+                    render_pass.draw(0..6, start..end);
+                },
+                None => log::warn!("sprite atlas {} missing", run[0].0),
+            }
+            start = end;
+        }
+        render_pass.pop_debug_group();
+        #[cfg(feature = "test-capture")]
+        crate::capture::record(|| crate::capture::Op::Sprites { instances: self.sprites.len() as u32, draws: self.sprites.chunk_by(|(a, _), (b, _)| a == b).count() as u32 });
+        self.sprites.clear();
+    }
+}
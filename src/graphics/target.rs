@@ -0,0 +1,50 @@
+use super::{Graphics, msaa::create_texture};
+
+//offscreen color target with optional depth, e.g. for post processing,
+//sampling needs TEXTURE_BINDING and readback COPY_SRC in usage, RENDER_ATTACHMENT is always added
+pub struct RenderTarget
+{
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    depth_format: Option<wgpu::TextureFormat>,
+    size: (u32, u32),
+    color: (wgpu::Texture, wgpu::TextureView),
+    depth: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl RenderTarget
+{
+    pub(crate) fn new(graphics: &Graphics, size: (u32, u32), format: wgpu::TextureFormat, usage: wgpu::TextureUsages) -> Self
+    {
+        let size = (size.0.max(1), size.1.max(1));
+        let usage = usage | wgpu::TextureUsages::RENDER_ATTACHMENT;
+        let color = create_texture(graphics, format, 1, size, usage);
+        Self { format, usage, depth_format: None, size, color, depth: None }
+    }
+
+    pub fn with_depth(mut self, graphics: &Graphics, format: wgpu::TextureFormat) -> Self
+    {
+        self.depth_format = Some(format);
+        self.depth = Some(create_texture(graphics, format, 1, self.size, wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING));
+        self
+    }
+
+    //recreates the textures if the size changed, returns true if so and bind groups need updating
+    pub fn resize(&mut self, graphics: &Graphics, size: (u32, u32)) -> bool
+    {
+        let size = (size.0.max(1), size.1.max(1));
+        if size == self.size { return false; }
+        let mut target = Self::new(graphics, size, self.format, self.usage);
+        if let Some(format) = self.depth_format { target = target.with_depth(graphics, format); }
+        *self = target;
+        true
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat { self.format }
+    pub fn usage(&self) -> wgpu::TextureUsages { self.usage }
+    pub fn size(&self) -> (u32, u32) { self.size }
+    pub fn texture(&self) -> &wgpu::Texture { &self.color.0 }
+    pub fn view(&self) -> &wgpu::TextureView { &self.color.1 }
+    pub fn depth_texture(&self) -> Option<&wgpu::Texture> { self.depth.as_ref().map(|(texture, _)| texture) }
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> { self.depth.as_ref().map(|(_, view)| view) }
+}
@@ -0,0 +1,216 @@
+use std::io::Cursor;
+use image::AnimationDecoder;
+use super::Graphics;
+use crate::Result;
+
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+//what to do with images beyond max_texture_dimension_2d (8192 on most desktops, 2048 on some webgl2)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizePolicy
+{
+    Reject,
+    Downscale, //keeps the aspect ratio
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadOptions
+{
+    pub size: SizePolicy,
+    pub premultiply: bool, //for AlphaMode::Premultiplied pipelines, avoids dark fringes at transparent edges
+}
+
+impl Default for LoadOptions
+{
+    fn default() -> Self { Self { size: SizePolicy::Reject, premultiply: false } }
+}
+
+//multiplies the srgb encoded rgb by alpha in linear space
+pub fn premultiply(rgba: &mut [u8])
+{
+    let to_linear = |c: u8| { let c = c as f32 / 255.0; if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) } };
+    let to_srgb = |c: f32| { let c = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }; (c * 255.0).round().clamp(0.0, 255.0) as u8 };
+    for pixel in rgba.chunks_exact_mut(4)
+    {
+        let alpha = pixel[3] as f32 / 255.0;
+        if alpha >= 1.0 { continue; }
+        for c in &mut pixel[..3] { *c = to_srgb(to_linear(*c) * alpha); }
+    }
+}
+
+fn check_size(graphics: &Graphics, (width, height): (u32, u32)) -> Result<()>
+{
+    let max = graphics.device.limits().max_texture_dimension_2d;
+    if width > max || height > max { Err(crate::Error::TextureSize { size: (width, height), max }) } else { Ok(()) }
+}
+
+//frames beyond max_texture_array_layers (256 by default) are rejected regardless of the size policy
+fn check_layers(graphics: &Graphics, layers: usize) -> Result<()>
+{
+    let max = graphics.device.limits().max_texture_array_layers;
+    if layers > max as usize
+    {
+        log::error!("{layers} frames exceed the {max} texture array layers");
+        return Err(crate::Error::Loader("too many frames for a texture array"));
+    }
+    Ok(())
+}
+
+//animation frames beyond max_texture_dimension_2d, downscaled like load_texture_with
+fn fit_frame(graphics: &Graphics, frame: image::RgbaImage, policy: SizePolicy) -> Result<image::RgbaImage>
+{
+    let max = graphics.device.limits().max_texture_dimension_2d;
+    let (width, height) = frame.dimensions();
+    if policy == SizePolicy::Downscale && (width > max || height > max)
+    {
+        let scale = max as f32 / width.max(height) as f32;
+        let size = (((width as f32 * scale) as u32).max(1), ((height as f32 * scale) as u32).max(1));
+        return Ok(image::imageops::resize(&frame, size.0, size.1, image::imageops::FilterType::Triangle));
+    }
+    check_size(graphics, (width, height))?;
+    Ok(frame)
+}
+
+//uploads tightly packed rgba layers of equal size
+pub fn create_texture(graphics: &Graphics, (width, height): (u32, u32), layers: u32, rgba: &[u8]) -> (wgpu::Texture, wgpu::TextureView)
+{
+    use wgpu::util::DeviceExt;
+    let texture_descr = wgpu::TextureDescriptor
+    {
+        label: None,
+        size: wgpu::Extent3d { width, height, depth_or_array_layers: layers },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    };
+    let texture = graphics.device.create_texture_with_data(&graphics.queue, &texture_descr, wgpu::util::TextureDataOrder::LayerMajor, rgba);
+    let view_descr = wgpu::TextureViewDescriptor
+    {
+        dimension: Some(if layers > 1 { wgpu::TextureViewDimension::D2Array } else { wgpu::TextureViewDimension::D2 }),
+        ..Default::default()
+    };
+    let view = texture.create_view(&view_descr);
+    (texture, view)
+}
+
+//decodes any format supported by the image crate, rejects oversized images
+pub fn load_texture(graphics: &Graphics, bytes: &[u8]) -> Result<(wgpu::Texture, wgpu::TextureView)>
+{
+    load_texture_with(graphics, bytes, LoadOptions::default())
+}
+
+pub fn load_texture_with(graphics: &Graphics, bytes: &[u8], options: LoadOptions) -> Result<(wgpu::Texture, wgpu::TextureView)>
+{
+    let mut image = image::load_from_memory(bytes)?;
+    let max = graphics.device.limits().max_texture_dimension_2d;
+    if options.size == SizePolicy::Downscale && (image.width() > max || image.height() > max)
+    {
+        log::warn!("downscaling {}x{} image to fit {max}", image.width(), image.height());
+        image = image.resize(max, max, image::imageops::FilterType::Triangle);
+    }
+    let mut image = image.into_rgba8();
+    check_size(graphics, image.dimensions())?;
+    if options.premultiply { premultiply(&mut image); }
+    Ok(create_texture(graphics, image.dimensions(), 1, image.as_raw()))
+}
+
+//texture array with one layer per frame
+pub struct AnimatedTexture
+{
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    durations: Vec<f32>, //secs per frame
+    total: f32,
+}
+
+impl AnimatedTexture
+{
+    fn new(graphics: &Graphics, size: (u32, u32), frames: &[u8], durations: Vec<f32>) -> Self
+    {
+        //always an array view, GL does not like TextureArray with 1 element
+        let mut layers = durations.len() as u32;
+        let mut data = frames.to_vec();
+        if layers == 1 && graphics.backend() == wgpu::Backend::Gl
+        {
+            data.extend_from_slice(frames);
+            layers = 2;
+        }
+        let (texture, _) = create_texture(graphics, size, layers, &data);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor { dimension: Some(wgpu::TextureViewDimension::D2Array), ..Default::default() });
+        let total = durations.iter().sum();
+        Self { texture, view, durations, total }
+    }
+
+    pub fn frames(&self) -> usize { self.durations.len() }
+    pub fn duration(&self) -> f32 { self.total }
+    pub fn frame_duration(&self, frame: usize) -> f32 { self.durations[frame] }
+
+    //looping layer for the elapsed secs
+    pub fn layer_at(&self, elapsed: f32) -> u32
+    {
+        if self.total <= 0.0 { return 0; }
+        let mut t = elapsed.rem_euclid(self.total);
+        for (i, &duration) in self.durations.iter().enumerate()
+        {
+            if t < duration { return i as u32; }
+            t -= duration;
+        }
+        self.durations.len() as u32 - 1
+    }
+}
+
+pub fn load_gif(graphics: &Graphics, bytes: &[u8]) -> Result<AnimatedTexture>
+{
+    load_gif_with(graphics, bytes, LoadOptions::default())
+}
+
+pub fn load_gif_with(graphics: &Graphics, bytes: &[u8], options: LoadOptions) -> Result<AnimatedTexture>
+{
+    let frames = image::codecs::gif::GifDecoder::new(Cursor::new(bytes))?.into_frames().collect_frames()?;
+    if frames.is_empty() { return Err(crate::Error::Loader("gif without frames")); }
+    check_layers(graphics, frames.len())?;
+    let mut size = (0, 0);
+    let mut data = Vec::new();
+    let mut durations = Vec::with_capacity(frames.len());
+    for frame in frames
+    {
+        let (num, denom) = frame.delay().numer_denom_ms();
+        durations.push(num as f32 / denom.max(1) as f32 / 1e3);
+        let buffer = fit_frame(graphics, frame.into_buffer(), options.size)?;
+        size = buffer.dimensions();
+        data.extend_from_slice(buffer.as_raw());
+    }
+    if options.premultiply { premultiply(&mut data); }
+    Ok(AnimatedTexture::new(graphics, size, &data, durations))
+}
+
+//splits the sheet row-major into frames of frame_size
+pub fn load_sprite_sheet(graphics: &Graphics, bytes: &[u8], frame_size: (u32, u32), frame_duration: f32) -> Result<AnimatedTexture>
+{
+    load_sprite_sheet_with(graphics, bytes, frame_size, frame_duration, LoadOptions::default())
+}
+
+pub fn load_sprite_sheet_with(graphics: &Graphics, bytes: &[u8], (frame_width, frame_height): (u32, u32), frame_duration: f32, options: LoadOptions) -> Result<AnimatedTexture>
+{
+    let image = image::load_from_memory(bytes)?.into_rgba8();
+    let (columns, rows) = (image.width() / frame_width.max(1), image.height() / frame_height.max(1));
+    if columns == 0 || rows == 0 { return Err(crate::Error::Loader("sprite sheet smaller than frame")); }
+    check_layers(graphics, (columns * rows) as usize)?;
+    let mut size = (0, 0);
+    let mut data = Vec::new();
+    for row in 0..rows
+    {
+        for column in 0..columns
+        {
+            let frame = image::imageops::crop_imm(&image, column * frame_width, row * frame_height, frame_width, frame_height).to_image();
+            let frame = fit_frame(graphics, frame, options.size)?;
+            size = frame.dimensions();
+            data.extend_from_slice(frame.as_raw());
+        }
+    }
+    if options.premultiply { premultiply(&mut data); }
+    Ok(AnimatedTexture::new(graphics, size, &data, vec![frame_duration; (columns * rows) as usize]))
+}
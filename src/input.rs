@@ -1,24 +1,114 @@
+use std::collections::HashSet;
 use gru_misc::math::Vec2;
-use winit::{window::{Window, CursorGrabMode}, event::{DeviceEvent, WindowEvent}, dpi::PhysicalPosition};
+use winit::{window::{Window, CursorGrabMode}, event::{DeviceEvent, WindowEvent, TouchPhase}, dpi::PhysicalPosition};
 #[cfg(feature = "ui")]
 use gru_ui::event::{HardwareEvent, MouseButton, Key};
 #[cfg(feature = "ui")]
 use winit::{event::{ElementState, MouseButton as WinitMouseButton, MouseScrollDelta}, keyboard::{PhysicalKey, KeyCode}};
 
+//ctrl, shift, alt and super bitflags, HardwareEvent::Key cannot carry them so query modifiers() instead
+pub use winit::keyboard::ModifiersState as Modifiers;
+
+#[cfg(feature = "gamepad")]
+mod gamepad;
+#[cfg(feature = "gamepad")]
+pub use gamepad::{Gamepad, GamepadButton, GamepadId};
+
 pub enum RawEvent
 {
     Device(DeviceEvent),
     Window(WindowEvent),
 }
 
+//physical button, independent of the ui event conversion
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "storage", derive(serde::Serialize, serde::Deserialize))]
+pub enum Button
+{
+    Key(winit::keyboard::KeyCode),
+    Mouse(winit::event::MouseButton),
+    #[cfg(feature = "gamepad")]
+    Gamepad(GamepadButton), //any connected pad
+}
+
+//keyboard navigation derived from Tab, Shift-Tab, Enter, Space and the arrows,
+//the raw keys are still forwarded, gru_ui's HardwareEvent has no focus variants
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusIntent
+{
+    Next,
+    Prev,
+    Activate,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+//what happens to motion and scroll events beyond the per frame cap, discrete events are never capped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow
+{
+    Coalesce, //merged into the previous event if it is of the same kind, else kept
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct EventCap
+{
+    max: usize,
+    overflow: Overflow,
+}
+
+//active contact point
+#[derive(Clone, Copy, Debug)]
+pub struct Touch
+{
+    pub id: u64,
+    pub pos: Vec2,
+    pub start: Vec2, //where the finger went down
+}
+
+fn button_event(event: &RawEvent) -> Option<(Button, bool)>
+{
+    match event
+    {
+        RawEvent::Window(WindowEvent::KeyboardInput { event, .. }) if !event.repeat => match event.physical_key
+        {
+            winit::keyboard::PhysicalKey::Code(code) => Some((Button::Key(code), event.state.is_pressed())),
+            _ => None,
+        },
+        RawEvent::Window(WindowEvent::MouseInput { state, button, .. }) => Some((Button::Mouse(*button), state.is_pressed())),
+        _ => None,
+    }
+}
+
 pub struct Input
 {
     cam_mode: bool,
     pub pointer_pos: Vec2,
+    scroll_line_height: f32, //pixels per scroll line for pixel precise wheels and trackpads
+    buttons: Vec<(Button, bool)>,
+    held: HashSet<Button>, //survives clear
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    focus_intents: Vec<FocusIntent>,
+    modifiers: Modifiers,
+    #[cfg(all(feature = "ui", feature = "clipboard"))]
+    paste_shortcut: bool,
+    touches: Vec<Touch>,
+    touch_events: Vec<(u64, TouchPhase, Vec2)>,
+    primary_touch: Option<u64>, //drives the pointer
+    #[cfg(feature = "gamepad")]
+    gamepads: gamepad::Gamepads,
+    event_cap: Option<EventCap>,
+    motion_events: usize, //capped events this frame
     #[cfg(not(feature = "ui"))]
     events: Vec<RawEvent>,
     #[cfg(feature = "ui")]
     events: Vec<HardwareEvent>,
+    #[cfg(feature = "ui")]
+    injected: Vec<HardwareEvent>, //moved into events after the current frame
 }
 
 impl Input
@@ -29,27 +119,242 @@ impl Input
         {
             cam_mode: false,
             pointer_pos: Vec2(0.0, 0.0),
+            scroll_line_height: 20.0,
+            buttons: Vec::new(),
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            focus_intents: Vec::new(),
+            modifiers: Modifiers::empty(),
+            #[cfg(all(feature = "ui", feature = "clipboard"))]
+            paste_shortcut: false,
+            touches: Vec::new(),
+            touch_events: Vec::new(),
+            primary_touch: None,
+            #[cfg(feature = "gamepad")]
+            gamepads: gamepad::Gamepads::new(),
+            event_cap: None,
+            motion_events: 0,
             events: Vec::new(),
+            #[cfg(feature = "ui")]
+            injected: Vec::new(),
         }
     }
 
     pub(crate) fn event(&mut self, event: RawEvent)
     {
+        if let Some((button, pressed)) = button_event(&event) { self.button(button, pressed); }
+        match &event
+        {
+            RawEvent::Window(WindowEvent::Focused(false)) =>
+            {
+                self.release_all();
+                self.modifiers = Modifiers::empty();
+            },
+            RawEvent::Window(WindowEvent::ModifiersChanged(modifiers)) => self.modifiers = modifiers.state(),
+            _ => {},
+        }
+        if let RawEvent::Window(WindowEvent::KeyboardInput { event, .. }) = &event && event.state.is_pressed() && !self.cam_mode
+            && let winit::keyboard::PhysicalKey::Code(code) = event.physical_key
+            && let Some(intent) = self.focus_intent(code)
+        {
+            self.focus_intents.push(intent);
+        }
+        #[cfg(all(feature = "ui", feature = "clipboard"))]
+        if let RawEvent::Window(WindowEvent::KeyboardInput { event, .. }) = &event && event.state.is_pressed()
+            && event.physical_key == winit::keyboard::PhysicalKey::Code(winit::keyboard::KeyCode::KeyV)
+            && if cfg!(target_os = "macos") { self.modifiers.super_key() } else { self.modifiers.control_key() }
+        {
+            self.paste_shortcut = true;
+        }
+        if let RawEvent::Window(WindowEvent::Touch(touch)) = &event { self.touch(touch.id, touch.phase, Vec2(touch.location.x as f32, touch.location.y as f32)); }
         #[cfg(not(feature = "ui"))]
         {
             if let RawEvent::Window(WindowEvent::CursorMoved { position, .. }) = &event && !self.cam_mode
             {
                 self.pointer_pos = Vec2(position.x as f32, position.y as f32);
             }
-            self.events.push(event);
+            push_capped(&mut self.events, &mut self.motion_events, self.event_cap, event);
         }
         #[cfg(feature = "ui")]
-        convert(self.cam_mode, &mut self.pointer_pos, &event, |event| self.events.push(event));
+        convert(self.cam_mode, self.scroll_line_height, &mut self.pointer_pos, &event, |event| push_capped(&mut self.events, &mut self.motion_events, self.event_cap, event));
     }
 
+    fn button(&mut self, button: Button, pressed: bool)
+    {
+        if pressed
+        {
+            if self.held.insert(button) { self.pressed.insert(button); }
+        } else if self.held.remove(&button) { self.released.insert(button); }
+        self.buttons.push((button, pressed));
+    }
+
+    //releases held buttons, key ups are lost while unfocused
+    fn release_all(&mut self)
+    {
+        let held: Vec<_> = self.held.drain().collect();
+        for button in held
+        {
+            self.released.insert(button);
+            self.buttons.push((button, false));
+        }
+    }
+
+    fn focus_intent(&self, code: winit::keyboard::KeyCode) -> Option<FocusIntent>
+    {
+        use winit::keyboard::KeyCode as Code;
+        let shift = self.key_held(Code::ShiftLeft) || self.key_held(Code::ShiftRight);
+        match code
+        {
+            Code::Tab if shift => Some(FocusIntent::Prev),
+            Code::Tab => Some(FocusIntent::Next),
+            Code::Enter | Code::NumpadEnter | Code::Space => Some(FocusIntent::Activate),
+            Code::ArrowUp => Some(FocusIntent::Up),
+            Code::ArrowDown => Some(FocusIntent::Down),
+            Code::ArrowLeft => Some(FocusIntent::Left),
+            Code::ArrowRight => Some(FocusIntent::Right),
+            _ => None,
+        }
+    }
+
+    //navigation intents of this frame including key repeats, only while the window has focus
+    pub fn focus_intents(&self) -> &[FocusIntent]
+    {
+        &self.focus_intents
+    }
+
+    //as of the latest processed event, winit sends ModifiersChanged before the key or click it applies to
+    pub fn modifiers(&self) -> Modifiers
+    {
+        self.modifiers
+    }
+
+    pub fn held(&self, button: Button) -> bool { self.held.contains(&button) }
+    pub fn key_held(&self, key: winit::keyboard::KeyCode) -> bool { self.held(Button::Key(key)) }
+    pub fn mouse_held(&self, button: winit::event::MouseButton) -> bool { self.held(Button::Mouse(button)) }
+    //valid for the current frame
+    pub fn just_pressed(&self, button: Button) -> bool { self.pressed.contains(&button) }
+    pub fn just_released(&self, button: Button) -> bool { self.released.contains(&button) }
+
+    //the first finger down acts as the pointer for single touch ui
+    fn touch(&mut self, id: u64, phase: TouchPhase, pos: Vec2)
+    {
+        self.touch_events.push((id, phase, pos));
+        match phase
+        {
+            TouchPhase::Started =>
+            {
+                self.touches.retain(|touch| touch.id != id);
+                self.touches.push(Touch { id, pos, start: pos });
+                if self.primary_touch.is_none() { self.primary_touch = Some(id); }
+            },
+            TouchPhase::Moved => if let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == id) { touch.pos = pos; },
+            TouchPhase::Ended | TouchPhase::Cancelled => self.touches.retain(|touch| touch.id != id),
+        }
+        if self.primary_touch != Some(id) || self.cam_mode { return; }
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) { self.primary_touch = None; }
+        #[cfg(not(feature = "ui"))]
+        { self.pointer_pos = pos; }
+        #[cfg(feature = "ui")]
+        {
+            let delta = pos - self.pointer_pos;
+            self.pointer_pos = pos;
+            match phase
+            {
+                TouchPhase::Started =>
+                {
+                    self.events.push(HardwareEvent::PointerMoved { pos, delta });
+                    self.events.push(HardwareEvent::PointerClicked { pos, button: MouseButton::Primary, pressed: true });
+                },
+                TouchPhase::Moved => self.events.push(HardwareEvent::PointerMoved { pos, delta }),
+                TouchPhase::Ended =>
+                {
+                    self.events.push(HardwareEvent::PointerClicked { pos, button: MouseButton::Primary, pressed: false });
+                    self.events.push(HardwareEvent::PointerGone);
+                },
+                //no release, a cancelled touch must not click
+                TouchPhase::Cancelled => self.events.push(HardwareEvent::PointerGone),
+            }
+        }
+    }
+
+    //limits mouse motion and scroll events per frame, e.g. after a long frame, None is unbounded
+    pub fn set_event_cap(&mut self, cap: Option<(usize, Overflow)>)
+    {
+        self.event_cap = cap.map(|(max, overflow)| EventCap { max, overflow });
+    }
+
+    //PixelDelta scrolls are divided by this into the line units of HardwareEvent::Scroll
+    pub fn scroll_line_height(&self) -> f32 { self.scroll_line_height }
+    pub fn set_scroll_line_height(&mut self, pixels: f32) { self.scroll_line_height = pixels.max(1.0); }
+
+    #[cfg(all(feature = "ui", feature = "clipboard"))]
+    pub(crate) fn take_paste_shortcut(&mut self) -> bool { std::mem::take(&mut self.paste_shortcut) }
+
+    //pasted text reaches the ui as chars in the current frame
+    #[cfg(all(feature = "ui", feature = "clipboard"))]
+    pub(crate) fn paste(&mut self, text: &str)
+    {
+        self.events.extend(text.chars().filter(|ch| !ch.is_control() || *ch == '\n').map(HardwareEvent::Char));
+    }
+
+    //all fingers currently down, in order of contact
+    pub fn touches(&self) -> &[Touch]
+    {
+        &self.touches
+    }
+
+    //(finger id, phase, position) of this frame, Cancelled means the contact is gone without a release
+    pub fn touch_events(&self) -> &[(u64, TouchPhase, Vec2)]
+    {
+        &self.touch_events
+    }
+
+    //called at the start of each frame
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn poll_gamepads(&mut self)
+    {
+        for (button, pressed) in self.gamepads.poll()
+        {
+            self.button(Button::Gamepad(button), pressed);
+            #[cfg(feature = "ui")]
+            if let Some(key) = gamepad_key(button) { self.events.push(HardwareEvent::Key { key, pressed }); }
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn gamepads(&self) -> impl Iterator<Item = &Gamepad> { self.gamepads.pads().iter() }
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_deadzone(&self) -> f32 { self.gamepads.deadzone() }
+    #[cfg(feature = "gamepad")]
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) { self.gamepads.set_deadzone(deadzone); }
+
     pub(crate) fn clear(&mut self)
     {
+        self.buttons.clear();
+        self.pressed.clear();
+        self.released.clear();
+        self.touch_events.clear();
+        self.focus_intents.clear();
+        self.motion_events = 0;
+        #[cfg(feature = "gamepad")]
+        self.gamepads.clear();
         self.events.clear();
+        #[cfg(feature = "ui")]
+        self.events.append(&mut self.injected);
+    }
+
+    //synthetic event, seen by the ui in the next frame like real input
+    #[cfg(feature = "ui")]
+    pub(crate) fn inject(&mut self, event: HardwareEvent)
+    {
+        self.injected.push(event);
+    }
+
+    //button presses and releases of this frame, without key repeats
+    pub fn buttons(&self) -> &[(Button, bool)]
+    {
+        &self.buttons
     }
 
     #[cfg(not(feature = "ui"))]
@@ -80,8 +385,207 @@ impl Input
     }
 }
 
+#[cfg(not(feature = "ui"))]
+type Event = RawEvent;
+#[cfg(feature = "ui")]
+type Event = HardwareEvent;
+
+fn push_capped(events: &mut Vec<Event>, count: &mut usize, cap: Option<EventCap>, event: Event)
+{
+    if !is_motion(&event) { return events.push(event); }
+    *count += 1;
+    match cap
+    {
+        Some(EventCap { max, overflow }) if *count > max => match overflow
+        {
+            Overflow::Coalesce => if !events.last_mut().is_some_and(|last| merge(last, &event)) { events.push(event); },
+            Overflow::Drop => {},
+        },
+        _ => events.push(event),
+    }
+}
+
+#[cfg(not(feature = "ui"))]
+fn is_motion(event: &RawEvent) -> bool
+{
+    matches!(event, RawEvent::Device(DeviceEvent::MouseMotion { .. }) | RawEvent::Window(WindowEvent::CursorMoved { .. } | WindowEvent::MouseWheel { .. }))
+}
+
+//true if new was folded into last
+#[cfg(not(feature = "ui"))]
+fn merge(last: &mut RawEvent, new: &RawEvent) -> bool
+{
+    use winit::event::MouseScrollDelta::*;
+    match (last, new)
+    {
+        (RawEvent::Device(DeviceEvent::MouseMotion { delta }), RawEvent::Device(DeviceEvent::MouseMotion { delta: new })) => *delta = (delta.0 + new.0, delta.1 + new.1),
+        (RawEvent::Window(WindowEvent::CursorMoved { position, .. }), RawEvent::Window(WindowEvent::CursorMoved { position: new, .. })) => *position = *new,
+        (RawEvent::Window(WindowEvent::MouseWheel { delta: LineDelta(x, y), .. }), RawEvent::Window(WindowEvent::MouseWheel { delta: LineDelta(dx, dy), .. })) => (*x, *y) = (*x + dx, *y + dy),
+        (RawEvent::Window(WindowEvent::MouseWheel { delta: PixelDelta(delta), .. }), RawEvent::Window(WindowEvent::MouseWheel { delta: PixelDelta(new), .. })) => (delta.x, delta.y) = (delta.x + new.x, delta.y + new.y),
+        _ => return false,
+    }
+    true
+}
+
 #[cfg(feature = "ui")]
-fn convert(cam_mode: bool, pointer_pos: &mut Vec2, raw_event: &RawEvent, mut accept: impl FnMut(HardwareEvent))
+fn is_motion(event: &HardwareEvent) -> bool
+{
+    matches!(event, HardwareEvent::PointerMoved { .. } | HardwareEvent::Scroll { .. } | HardwareEvent::RawMouseDelta(_))
+}
+
+#[cfg(feature = "ui")]
+fn merge(last: &mut HardwareEvent, new: &HardwareEvent) -> bool
+{
+    match (last, new)
+    {
+        (HardwareEvent::PointerMoved { pos, delta }, HardwareEvent::PointerMoved { pos: new_pos, delta: new_delta }) => (*pos, *delta) = (*new_pos, *delta + *new_delta),
+        (HardwareEvent::Scroll { pos, delta }, HardwareEvent::Scroll { pos: new_pos, delta: new_delta }) => (*pos, *delta) = (*new_pos, *delta + *new_delta),
+        (HardwareEvent::RawMouseDelta(delta), HardwareEvent::RawMouseDelta(new)) => *delta = *delta + *new,
+        _ => return false,
+    }
+    true
+}
+
+//d-pad navigates, south confirms, east cancels
+#[cfg(all(feature = "ui", feature = "gamepad"))]
+fn gamepad_key(button: GamepadButton) -> Option<Key>
+{
+    match button
+    {
+        GamepadButton::DPadUp => Some(Key::Up),
+        GamepadButton::DPadDown => Some(Key::Down),
+        GamepadButton::DPadLeft => Some(Key::Left),
+        GamepadButton::DPadRight => Some(Key::Right),
+        GamepadButton::South => Some(Key::Return),
+        GamepadButton::East => Some(Key::Escape),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "ui")]
+fn map_key(keycode: KeyCode) -> Option<Key>
+{
+    match keycode
+    {
+        KeyCode::Digit1 => Some(Key::Key1),
+        KeyCode::Digit2 => Some(Key::Key2),
+        KeyCode::Digit3 => Some(Key::Key3),
+        KeyCode::Digit4 => Some(Key::Key4),
+        KeyCode::Digit5 => Some(Key::Key5),
+        KeyCode::Digit6 => Some(Key::Key6),
+        KeyCode::Digit7 => Some(Key::Key7),
+        KeyCode::Digit8 => Some(Key::Key8),
+        KeyCode::Digit9 => Some(Key::Key9),
+        KeyCode::Digit0 => Some(Key::Key0),
+        KeyCode::KeyA => Some(Key::A),
+        KeyCode::KeyB => Some(Key::B),
+        KeyCode::KeyC => Some(Key::C),
+        KeyCode::KeyD => Some(Key::D),
+        KeyCode::KeyE => Some(Key::E),
+        KeyCode::KeyF => Some(Key::F),
+        KeyCode::KeyG => Some(Key::G),
+        KeyCode::KeyH => Some(Key::H),
+        KeyCode::KeyI => Some(Key::I),
+        KeyCode::KeyJ => Some(Key::J),
+        KeyCode::KeyK => Some(Key::K),
+        KeyCode::KeyL => Some(Key::L),
+        KeyCode::KeyM => Some(Key::M),
+        KeyCode::KeyN => Some(Key::N),
+        KeyCode::KeyO => Some(Key::O),
+        KeyCode::KeyP => Some(Key::P),
+        KeyCode::KeyQ => Some(Key::Q),
+        KeyCode::KeyR => Some(Key::R),
+        KeyCode::KeyS => Some(Key::S),
+        KeyCode::KeyT => Some(Key::T),
+        KeyCode::KeyU => Some(Key::U),
+        KeyCode::KeyV => Some(Key::V),
+        KeyCode::KeyW => Some(Key::W),
+        KeyCode::KeyX => Some(Key::X),
+        KeyCode::KeyY => Some(Key::Y),
+        KeyCode::KeyZ => Some(Key::Z),
+        KeyCode::Escape => Some(Key::Escape),
+        KeyCode::F1 => Some(Key::F1),
+        KeyCode::F2 => Some(Key::F2),
+        KeyCode::F3 => Some(Key::F3),
+        KeyCode::F4 => Some(Key::F4),
+        KeyCode::F5 => Some(Key::F5),
+        KeyCode::F6 => Some(Key::F6),
+        KeyCode::F7 => Some(Key::F7),
+        KeyCode::F8 => Some(Key::F8),
+        KeyCode::F9 => Some(Key::F9),
+        KeyCode::F10 => Some(Key::F10),
+        KeyCode::F11 => Some(Key::F11),
+        KeyCode::F12 => Some(Key::F12),
+        KeyCode::F13 => Some(Key::F13),
+        KeyCode::F14 => Some(Key::F14),
+        KeyCode::F15 => Some(Key::F15),
+        KeyCode::F16 => Some(Key::F16),
+        KeyCode::F17 => Some(Key::F17),
+        KeyCode::F18 => Some(Key::F18),
+        KeyCode::F19 => Some(Key::F19),
+        KeyCode::F20 => Some(Key::F20),
+        KeyCode::F21 => Some(Key::F21),
+        KeyCode::F22 => Some(Key::F22),
+        KeyCode::F23 => Some(Key::F23),
+        KeyCode::F24 => Some(Key::F24),
+        KeyCode::Pause => Some(Key::Pause),
+        KeyCode::Insert => Some(Key::Insert),
+        KeyCode::Home => Some(Key::Home),
+        KeyCode::Delete => Some(Key::Delete),
+        KeyCode::End => Some(Key::End),
+        KeyCode::PageDown => Some(Key::PageDown),
+        KeyCode::PageUp => Some(Key::PageUp),
+        KeyCode::ArrowLeft => Some(Key::Left),
+        KeyCode::ArrowUp => Some(Key::Up),
+        KeyCode::ArrowRight => Some(Key::Right),
+        KeyCode::ArrowDown => Some(Key::Down),
+        KeyCode::Backspace => Some(Key::Back),
+        KeyCode::Enter => Some(Key::Return),
+        KeyCode::Space => Some(Key::Space),
+        KeyCode::NumLock => Some(Key::Numlock),
+        KeyCode::Numpad0 => Some(Key::Numpad0),
+        KeyCode::Numpad1 => Some(Key::Numpad1),
+        KeyCode::Numpad2 => Some(Key::Numpad2),
+        KeyCode::Numpad3 => Some(Key::Numpad3),
+        KeyCode::Numpad4 => Some(Key::Numpad4),
+        KeyCode::Numpad5 => Some(Key::Numpad5),
+        KeyCode::Numpad6 => Some(Key::Numpad6),
+        KeyCode::Numpad7 => Some(Key::Numpad7),
+        KeyCode::Numpad8 => Some(Key::Numpad8),
+        KeyCode::Numpad9 => Some(Key::Numpad9),
+        KeyCode::NumpadAdd => Some(Key::NumpadAdd),
+        KeyCode::NumpadDivide => Some(Key::NumpadDivide),
+        KeyCode::NumpadDecimal => Some(Key::NumpadDecimal),
+        KeyCode::NumpadComma => Some(Key::NumpadComma),
+        KeyCode::NumpadEnter => Some(Key::NumpadEnter),
+        KeyCode::NumpadEqual => Some(Key::NumpadEquals),
+        KeyCode::NumpadMultiply => Some(Key::NumpadMultiply),
+        KeyCode::NumpadSubtract => Some(Key::NumpadSubtract),
+        KeyCode::AltLeft => Some(Key::LAlt),
+        KeyCode::ControlLeft => Some(Key::LControl),
+        KeyCode::ShiftLeft => Some(Key::LShift),
+        KeyCode::AltRight => Some(Key::RAlt),
+        KeyCode::ControlRight => Some(Key::RControl),
+        KeyCode::ShiftRight => Some(Key::RShift),
+        KeyCode::Tab => Some(Key::Tab),
+        KeyCode::CapsLock => Some(Key::Capital),
+        KeyCode::Minus => Some(Key::Minus),
+        KeyCode::Equal => Some(Key::Equals),
+        KeyCode::BracketLeft => Some(Key::LBracket),
+        KeyCode::BracketRight => Some(Key::RBracket),
+        KeyCode::Semicolon => Some(Key::Semicolon),
+        KeyCode::Quote => Some(Key::Apostrophe),
+        KeyCode::Backquote => Some(Key::Grave),
+        KeyCode::Comma => Some(Key::Comma),
+        KeyCode::Period => Some(Key::Period),
+        KeyCode::Slash => Some(Key::Slash),
+        KeyCode::Backslash => Some(Key::Backslash),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "ui")]
+fn convert(cam_mode: bool, line_height: f32, pointer_pos: &mut Vec2, raw_event: &RawEvent, mut accept: impl FnMut(HardwareEvent))
 {
     match raw_event
     {
@@ -120,116 +624,17 @@ fn convert(cam_mode: bool, pointer_pos: &mut Vec2, raw_event: &RawEvent, mut acc
             },
             WindowEvent::CursorLeft { .. } => accept(HardwareEvent::PointerGone),
             WindowEvent::MouseWheel { delta: MouseScrollDelta::LineDelta(dx, dy), .. } => accept(HardwareEvent::Scroll { pos: *pointer_pos, delta: Vec2(*dx, *dy) }),
+            //fractional lines keep trackpad scrolling smooth
+            WindowEvent::MouseWheel { delta: MouseScrollDelta::PixelDelta(delta), .. } =>
+            {
+                let delta = Vec2(delta.x as f32, delta.y as f32) * (1.0 / line_height);
+                accept(HardwareEvent::Scroll { pos: *pointer_pos, delta });
+            },
             WindowEvent::KeyboardInput { event, .. } =>
             {
                 if let PhysicalKey::Code(keycode) = event.physical_key
                 {
-                    let key = match keycode
-                    {
-                        KeyCode::Digit1 => Some(Key::Key1),
-                        KeyCode::Digit3 => Some(Key::Key3),
-                        KeyCode::Digit2 => Some(Key::Key2),
-                        KeyCode::Digit4 => Some(Key::Key4),
-                        KeyCode::Digit5 => Some(Key::Key5),
-                        KeyCode::Digit6 => Some(Key::Key6),
-                        KeyCode::Digit7 => Some(Key::Key7),
-                        KeyCode::Digit8 => Some(Key::Key8),
-                        KeyCode::Digit9 => Some(Key::Key9),
-                        KeyCode::Digit0 => Some(Key::Key0),
-                        KeyCode::KeyA => Some(Key::A),
-                        KeyCode::KeyB => Some(Key::B),
-                        KeyCode::KeyC => Some(Key::C),
-                        KeyCode::KeyD => Some(Key::D),
-                        KeyCode::KeyE => Some(Key::E),
-                        KeyCode::KeyF => Some(Key::F),
-                        KeyCode::KeyG => Some(Key::G),
-                        KeyCode::KeyH => Some(Key::H),
-                        KeyCode::KeyI => Some(Key::I),
-                        KeyCode::KeyJ => Some(Key::J),
-                        KeyCode::KeyK => Some(Key::K),
-                        KeyCode::KeyL => Some(Key::L),
-                        KeyCode::KeyM => Some(Key::M),
-                        KeyCode::KeyN => Some(Key::N),
-                        KeyCode::KeyO => Some(Key::O),
-                        KeyCode::KeyP => Some(Key::P),
-                        KeyCode::KeyQ => Some(Key::Q),
-                        KeyCode::KeyR => Some(Key::R),
-                        KeyCode::KeyS => Some(Key::S),
-                        KeyCode::KeyT => Some(Key::T),
-                        KeyCode::KeyU => Some(Key::U),
-                        KeyCode::KeyV => Some(Key::V),
-                        KeyCode::KeyW => Some(Key::W),
-                        KeyCode::KeyX => Some(Key::X),
-                        KeyCode::KeyY => Some(Key::Y),
-                        KeyCode::KeyZ => Some(Key::Z),
-                        KeyCode::Escape => Some(Key::Escape),
-                        KeyCode::F1 => Some(Key::F1),
-                        KeyCode::F2 => Some(Key::F2),
-                        KeyCode::F3 => Some(Key::F3),
-                        KeyCode::F4 => Some(Key::F4),
-                        KeyCode::F5 => Some(Key::F5),
-                        KeyCode::F6 => Some(Key::F6),
-                        KeyCode::F7 => Some(Key::F7),
-                        KeyCode::F8 => Some(Key::F8),
-                        KeyCode::F9 => Some(Key::F9),
-                        KeyCode::F10 => Some(Key::F10),
-                        KeyCode::F11 => Some(Key::F11),
-                        KeyCode::F12 => Some(Key::F12),
-                        KeyCode::F13 => Some(Key::F13),
-                        KeyCode::F14 => Some(Key::F14),
-                        KeyCode::F15 => Some(Key::F15),
-                        KeyCode::F16 => Some(Key::F16),
-                        KeyCode::F17 => Some(Key::F17),
-                        KeyCode::F18 => Some(Key::F18),
-                        KeyCode::F19 => Some(Key::F19),
-                        KeyCode::F20 => Some(Key::F20),
-                        KeyCode::F21 => Some(Key::F21),
-                        KeyCode::F22 => Some(Key::F22),
-                        KeyCode::F23 => Some(Key::F23),
-                        KeyCode::F24 => Some(Key::F24),
-                        KeyCode::Pause => Some(Key::Pause),
-                        KeyCode::Insert => Some(Key::Insert),
-                        KeyCode::Home => Some(Key::Home),
-                        KeyCode::Delete => Some(Key::Delete),
-                        KeyCode::End => Some(Key::End),
-                        KeyCode::PageDown => Some(Key::PageDown),
-                        KeyCode::PageUp => Some(Key::PageUp),
-                        KeyCode::ArrowLeft => Some(Key::Left),
-                        KeyCode::ArrowUp => Some(Key::Up),
-                        KeyCode::ArrowRight => Some(Key::Right),
-                        KeyCode::ArrowDown => Some(Key::Down),
-                        KeyCode::Backspace => Some(Key::Back),
-                        KeyCode::Enter => Some(Key::Return),
-                        KeyCode::Space => Some(Key::Space),
-                        KeyCode::NumLock => Some(Key::Numlock),
-                        KeyCode::Numpad0 => Some(Key::Numpad0),
-                        KeyCode::Numpad1 => Some(Key::Numpad1),
-                        KeyCode::Numpad2 => Some(Key::Numpad2),
-                        KeyCode::Numpad3 => Some(Key::Numpad3),
-                        KeyCode::Numpad4 => Some(Key::Numpad4),
-                        KeyCode::Numpad5 => Some(Key::Numpad5),
-                        KeyCode::Numpad6 => Some(Key::Numpad6),
-                        KeyCode::Numpad7 => Some(Key::Numpad7),
-                        KeyCode::Numpad8 => Some(Key::Numpad8),
-                        KeyCode::Numpad9 => Some(Key::Numpad9),
-                        KeyCode::NumpadAdd => Some(Key::NumpadAdd),
-                        KeyCode::NumpadDivide => Some(Key::NumpadDivide),
-                        KeyCode::NumpadDecimal => Some(Key::NumpadDecimal),
-                        KeyCode::NumpadComma => Some(Key::NumpadComma),
-                        KeyCode::NumpadEnter => Some(Key::NumpadEnter),
-                        KeyCode::NumpadEqual => Some(Key::NumpadEquals),
-                        KeyCode::NumpadMultiply => Some(Key::NumpadMultiply),
-                        KeyCode::NumpadSubtract => Some(Key::NumpadSubtract),
-                        KeyCode::AltLeft => Some(Key::LAlt),
-                        KeyCode::ControlLeft => Some(Key::LControl),
-                        KeyCode::ShiftLeft => Some(Key::LShift),
-                        KeyCode::AltRight => Some(Key::RAlt),
-                        KeyCode::ControlRight => Some(Key::RControl),
-                        KeyCode::ShiftRight => Some(Key::RShift),
-                        KeyCode::Tab => Some(Key::Tab),
-                        _ => None
-                    };
-                    if let Some(key) = key
+                    if let Some(key) = map_key(keycode)
                     {
                         let event = HardwareEvent::Key { key, pressed: event.state == ElementState::Pressed };
                         accept(event);
@@ -246,3 +651,259 @@ fn convert(cam_mode: bool, pointer_pos: &mut Vec2, raw_event: &RawEvent, mut acc
         }
     }
 }
+
+#[cfg(all(test, feature = "ui"))]
+mod tests
+{
+    use super::*;
+
+    const KEY_CODES: &[KeyCode] =
+    &[
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+        KeyCode::Digit0,
+        KeyCode::KeyA,
+        KeyCode::KeyB,
+        KeyCode::KeyC,
+        KeyCode::KeyD,
+        KeyCode::KeyE,
+        KeyCode::KeyF,
+        KeyCode::KeyG,
+        KeyCode::KeyH,
+        KeyCode::KeyI,
+        KeyCode::KeyJ,
+        KeyCode::KeyK,
+        KeyCode::KeyL,
+        KeyCode::KeyM,
+        KeyCode::KeyN,
+        KeyCode::KeyO,
+        KeyCode::KeyP,
+        KeyCode::KeyQ,
+        KeyCode::KeyR,
+        KeyCode::KeyS,
+        KeyCode::KeyT,
+        KeyCode::KeyU,
+        KeyCode::KeyV,
+        KeyCode::KeyW,
+        KeyCode::KeyX,
+        KeyCode::KeyY,
+        KeyCode::KeyZ,
+        KeyCode::Escape,
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+        KeyCode::F13,
+        KeyCode::F14,
+        KeyCode::F15,
+        KeyCode::F16,
+        KeyCode::F17,
+        KeyCode::F18,
+        KeyCode::F19,
+        KeyCode::F20,
+        KeyCode::F21,
+        KeyCode::F22,
+        KeyCode::F23,
+        KeyCode::F24,
+        KeyCode::Pause,
+        KeyCode::Insert,
+        KeyCode::Home,
+        KeyCode::Delete,
+        KeyCode::End,
+        KeyCode::PageDown,
+        KeyCode::PageUp,
+        KeyCode::ArrowLeft,
+        KeyCode::ArrowUp,
+        KeyCode::ArrowRight,
+        KeyCode::ArrowDown,
+        KeyCode::Backspace,
+        KeyCode::Enter,
+        KeyCode::Space,
+        KeyCode::NumLock,
+        KeyCode::Numpad0,
+        KeyCode::Numpad1,
+        KeyCode::Numpad2,
+        KeyCode::Numpad3,
+        KeyCode::Numpad4,
+        KeyCode::Numpad5,
+        KeyCode::Numpad6,
+        KeyCode::Numpad7,
+        KeyCode::Numpad8,
+        KeyCode::Numpad9,
+        KeyCode::NumpadAdd,
+        KeyCode::NumpadDivide,
+        KeyCode::NumpadDecimal,
+        KeyCode::NumpadComma,
+        KeyCode::NumpadEnter,
+        KeyCode::NumpadEqual,
+        KeyCode::NumpadMultiply,
+        KeyCode::NumpadSubtract,
+        KeyCode::AltLeft,
+        KeyCode::ControlLeft,
+        KeyCode::ShiftLeft,
+        KeyCode::AltRight,
+        KeyCode::ControlRight,
+        KeyCode::ShiftRight,
+        KeyCode::Tab,
+        KeyCode::CapsLock,
+        KeyCode::Minus,
+        KeyCode::Equal,
+        KeyCode::BracketLeft,
+        KeyCode::BracketRight,
+        KeyCode::Semicolon,
+        KeyCode::Quote,
+        KeyCode::Backquote,
+        KeyCode::Comma,
+        KeyCode::Period,
+        KeyCode::Slash,
+        KeyCode::Backslash,
+    ];
+
+    //no wildcard: a new Key variant fails to compile here until it is mapped
+    fn key_index(key: Key) -> usize
+    {
+        match key
+        {
+            Key::Key1 => 0,
+            Key::Key2 => 1,
+            Key::Key3 => 2,
+            Key::Key4 => 3,
+            Key::Key5 => 4,
+            Key::Key6 => 5,
+            Key::Key7 => 6,
+            Key::Key8 => 7,
+            Key::Key9 => 8,
+            Key::Key0 => 9,
+            Key::A => 10,
+            Key::B => 11,
+            Key::C => 12,
+            Key::D => 13,
+            Key::E => 14,
+            Key::F => 15,
+            Key::G => 16,
+            Key::H => 17,
+            Key::I => 18,
+            Key::J => 19,
+            Key::K => 20,
+            Key::L => 21,
+            Key::M => 22,
+            Key::N => 23,
+            Key::O => 24,
+            Key::P => 25,
+            Key::Q => 26,
+            Key::R => 27,
+            Key::S => 28,
+            Key::T => 29,
+            Key::U => 30,
+            Key::V => 31,
+            Key::W => 32,
+            Key::X => 33,
+            Key::Y => 34,
+            Key::Z => 35,
+            Key::Escape => 36,
+            Key::F1 => 37,
+            Key::F2 => 38,
+            Key::F3 => 39,
+            Key::F4 => 40,
+            Key::F5 => 41,
+            Key::F6 => 42,
+            Key::F7 => 43,
+            Key::F8 => 44,
+            Key::F9 => 45,
+            Key::F10 => 46,
+            Key::F11 => 47,
+            Key::F12 => 48,
+            Key::F13 => 49,
+            Key::F14 => 50,
+            Key::F15 => 51,
+            Key::F16 => 52,
+            Key::F17 => 53,
+            Key::F18 => 54,
+            Key::F19 => 55,
+            Key::F20 => 56,
+            Key::F21 => 57,
+            Key::F22 => 58,
+            Key::F23 => 59,
+            Key::F24 => 60,
+            Key::Pause => 61,
+            Key::Insert => 62,
+            Key::Home => 63,
+            Key::Delete => 64,
+            Key::End => 65,
+            Key::PageDown => 66,
+            Key::PageUp => 67,
+            Key::Left => 68,
+            Key::Up => 69,
+            Key::Right => 70,
+            Key::Down => 71,
+            Key::Back => 72,
+            Key::Return => 73,
+            Key::Space => 74,
+            Key::Numlock => 75,
+            Key::Numpad0 => 76,
+            Key::Numpad1 => 77,
+            Key::Numpad2 => 78,
+            Key::Numpad3 => 79,
+            Key::Numpad4 => 80,
+            Key::Numpad5 => 81,
+            Key::Numpad6 => 82,
+            Key::Numpad7 => 83,
+            Key::Numpad8 => 84,
+            Key::Numpad9 => 85,
+            Key::NumpadAdd => 86,
+            Key::NumpadDivide => 87,
+            Key::NumpadDecimal => 88,
+            Key::NumpadComma => 89,
+            Key::NumpadEnter => 90,
+            Key::NumpadEquals => 91,
+            Key::NumpadMultiply => 92,
+            Key::NumpadSubtract => 93,
+            Key::LAlt => 94,
+            Key::LControl => 95,
+            Key::LShift => 96,
+            Key::RAlt => 97,
+            Key::RControl => 98,
+            Key::RShift => 99,
+            Key::Tab => 100,
+            Key::Capital => 101,
+            Key::Minus => 102,
+            Key::Equals => 103,
+            Key::LBracket => 104,
+            Key::RBracket => 105,
+            Key::Semicolon => 106,
+            Key::Apostrophe => 107,
+            Key::Grave => 108,
+            Key::Comma => 109,
+            Key::Period => 110,
+            Key::Slash => 111,
+            Key::Backslash => 112,
+        }
+    }
+
+    #[test]
+    fn every_key_is_mapped()
+    {
+        let mut produced = [false; 113];
+        for &keycode in KEY_CODES
+        {
+            if let Some(key) = map_key(keycode) { produced[key_index(key)] = true; }
+        }
+        let missing: Vec<_> = produced.iter().enumerate().filter(|(_, produced)| !**produced).map(|(index, _)| index).collect();
+        assert!(missing.is_empty(), "unmapped key indices {missing:?}");
+    }
+}
@@ -0,0 +1,156 @@
+use gru_misc::math::Vec2;
+pub use gilrs::{Button as GamepadButton, GamepadId};
+
+//controller state, sticks with y up in -1..1 after the deadzone
+pub struct Gamepad
+{
+    id: GamepadId,
+    name: String,
+    connected: bool,
+    down: Vec<GamepadButton>,
+    pressed: Vec<GamepadButton>,
+    released: Vec<GamepadButton>,
+    raw_sticks: [Vec2; 2],
+    sticks: [Vec2; 2],
+    triggers: [f32; 2],
+}
+
+impl Gamepad
+{
+    fn new(id: GamepadId, name: String) -> Self
+    {
+        let zero = Vec2(0.0, 0.0);
+        Self { id, name, connected: true, down: Vec::new(), pressed: Vec::new(), released: Vec::new(), raw_sticks: [zero, zero], sticks: [zero, zero], triggers: [0.0, 0.0] }
+    }
+
+    pub fn id(&self) -> usize { self.id.into() }
+    pub fn name(&self) -> &str { &self.name }
+    pub fn is_connected(&self) -> bool { self.connected }
+    pub fn is_down(&self, button: GamepadButton) -> bool { self.down.contains(&button) }
+    pub fn pressed(&self, button: GamepadButton) -> bool { self.pressed.contains(&button) }
+    pub fn released(&self, button: GamepadButton) -> bool { self.released.contains(&button) }
+    pub fn buttons_down(&self) -> &[GamepadButton] { &self.down }
+    pub fn left_stick(&self) -> Vec2 { self.sticks[0] }
+    pub fn right_stick(&self) -> Vec2 { self.sticks[1] }
+    pub fn left_trigger(&self) -> f32 { self.triggers[0] }
+    pub fn right_trigger(&self) -> f32 { self.triggers[1] }
+
+    fn apply_deadzone(&mut self, deadzone: f32)
+    {
+        for (stick, raw) in self.sticks.iter_mut().zip(self.raw_sticks)
+        {
+            let len = (raw.0 * raw.0 + raw.1 * raw.1).sqrt();
+            *stick = if len <= deadzone { Vec2(0.0, 0.0) } else { raw * ((len - deadzone) / (1.0 - deadzone).max(1e-6) / len).min(1.0 / len) };
+        }
+    }
+}
+
+pub(crate) struct Gamepads
+{
+    gilrs: Option<gilrs::Gilrs>,
+    pads: Vec<Gamepad>,
+    deadzone: f32,
+}
+
+//(button, pressed) of all pads in the order of the last poll
+pub(crate) type Transitions = Vec<(GamepadButton, bool)>;
+
+impl Gamepads
+{
+    pub(crate) fn new() -> Self
+    {
+        let gilrs = match gilrs::Gilrs::new()
+        {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) =>
+            {
+                log::warn!("gamepads unavailable: {err}");
+                None
+            },
+        };
+        let mut gamepads = Self { gilrs, pads: Vec::new(), deadzone: 0.15 };
+        let connected: Vec<_> = gamepads.gilrs.iter().flat_map(|gilrs| gilrs.gamepads().map(|(id, pad)| (id, pad.name().to_string()))).collect();
+        for (id, name) in connected { gamepads.pads.push(Gamepad::new(id, name)); }
+        gamepads
+    }
+
+    pub(crate) fn pads(&self) -> &[Gamepad] { &self.pads }
+    pub(crate) fn deadzone(&self) -> f32 { self.deadzone }
+    pub(crate) fn set_deadzone(&mut self, deadzone: f32) { self.deadzone = deadzone.clamp(0.0, 0.99); }
+
+    fn pad(&mut self, id: GamepadId) -> &mut Gamepad
+    {
+        match self.pads.iter().position(|pad| pad.id == id)
+        {
+            Some(i) => &mut self.pads[i],
+            None =>
+            {
+                let name = self.gilrs.as_ref().map(|gilrs| gilrs.gamepad(id).name().to_string()).unwrap_or_default();
+                self.pads.push(Gamepad::new(id, name));
+                self.pads.last_mut().unwrap()
+            },
+        }
+    }
+
+    //drains pending events, handles hot plugging
+    pub(crate) fn poll(&mut self) -> Transitions
+    {
+        let mut transitions = Vec::new();
+        let Some(mut gilrs) = self.gilrs.take() else { return transitions; };
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event()
+        {
+            let pad = self.pad(id);
+            match event
+            {
+                gilrs::EventType::Connected => pad.connected = true,
+                gilrs::EventType::Disconnected =>
+                {
+                    pad.connected = false;
+                    for button in pad.down.drain(..)
+                    {
+                        pad.released.push(button);
+                        transitions.push((button, false));
+                    }
+                    pad.raw_sticks = [Vec2(0.0, 0.0); 2];
+                    pad.triggers = [0.0; 2];
+                },
+                gilrs::EventType::ButtonPressed(button, _) if !pad.down.contains(&button) =>
+                {
+                    pad.down.push(button);
+                    pad.pressed.push(button);
+                    transitions.push((button, true));
+                },
+                gilrs::EventType::ButtonReleased(button, _) if pad.down.contains(&button) =>
+                {
+                    pad.down.retain(|b| *b != button);
+                    pad.released.push(button);
+                    transitions.push((button, false));
+                },
+                gilrs::EventType::ButtonChanged(GamepadButton::LeftTrigger2, value, _) => pad.triggers[0] = value,
+                gilrs::EventType::ButtonChanged(GamepadButton::RightTrigger2, value, _) => pad.triggers[1] = value,
+                gilrs::EventType::AxisChanged(axis, value, _) => match axis
+                {
+                    gilrs::Axis::LeftStickX => pad.raw_sticks[0].0 = value,
+                    gilrs::Axis::LeftStickY => pad.raw_sticks[0].1 = value,
+                    gilrs::Axis::RightStickX => pad.raw_sticks[1].0 = value,
+                    gilrs::Axis::RightStickY => pad.raw_sticks[1].1 = value,
+                    _ => {},
+                },
+                _ => {},
+            }
+        }
+        self.gilrs = Some(gilrs);
+        let deadzone = self.deadzone;
+        for pad in &mut self.pads { pad.apply_deadzone(deadzone); }
+        transitions
+    }
+
+    pub(crate) fn clear(&mut self)
+    {
+        for pad in &mut self.pads
+        {
+            pad.pressed.clear();
+            pad.released.clear();
+        }
+    }
+}
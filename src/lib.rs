@@ -10,6 +10,9 @@ pub use rodio;
 mod basics;
 pub use basics::time;
 pub mod input;
+pub mod action;
+pub mod stats;
+pub mod rng;
 pub mod graphics;
 #[cfg(feature = "ui")]
 pub mod ui_render;
@@ -17,9 +20,15 @@ pub mod ui_render;
 pub mod storage;
 #[cfg(feature = "file")]
 pub mod file;
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+#[cfg(feature = "test-capture")]
+pub mod capture;
 
-use std::sync::Arc;
-use winit::{application::ApplicationHandler, event::{WindowEvent, StartCause}, event_loop::{EventLoop, ActiveEventLoop, EventLoopProxy}, window::Window};
+use std::{sync::Arc, rc::Rc, cell::RefCell};
+use winit::{application::ApplicationHandler, event::{WindowEvent, StartCause}, event_loop::{EventLoop, ActiveEventLoop, EventLoopProxy, ControlFlow}, window::{Window, CursorIcon}};
 
 #[derive(Debug)]
 pub enum SurfaceError
@@ -28,42 +37,193 @@ pub enum SurfaceError
     Validation,
 }
 
+#[derive(Debug)]
+pub enum BufferError
+{
+    OutOfBounds { offset: u64, len: u64, size: u64 },
+    Unaligned { offset: u64, len: u64 },
+    NotCopyDst,
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error
 {
     #[cfg(feature = "file")]
     #[error("fs")]
     Io(#[from] std::io::Error),
-    #[cfg(feature = "file")]
+    #[cfg(any(feature = "file", feature = "image", feature = "ktx2"))]
     #[error("loader")]
     Loader(&'static str),
+    #[cfg(feature = "image")]
+    #[error("image")]
+    Image(#[from] image::ImageError),
+    #[cfg(feature = "image")]
+    #[error("texture size {size:?} exceeds {max}")]
+    TextureSize { size: (u32, u32), max: u32 },
+    #[cfg(feature = "ktx2")]
+    #[error("ktx2")]
+    Ktx2(#[from] ktx2::ParseError),
+    #[cfg(feature = "audio")]
+    #[error("audio decoder")]
+    Decoder(#[from] rodio::decoder::DecoderError),
+    #[cfg(feature = "ui")]
+    #[error("{sizes} distinct glyph layer sizes exceed the {max} ui atlases")]
+    GlyphAtlases { sizes: usize, max: usize },
+    #[error("unsupported texture format {0:?}")]
+    TextureFormat(wgpu::TextureFormat),
     #[error("creatae surface")]
     CreateSurface(#[from] wgpu::CreateSurfaceError),
     #[error("surface")]
     Surface(SurfaceError),
+    #[error("unsupported surface usage {0:?}")]
+    SurfaceUsage(wgpu::TextureUsages),
     #[error("adapter")]
     Adapter(#[from] wgpu::RequestAdapterError),
     #[error("device")]
     Device(#[from] wgpu::RequestDeviceError),
+    #[error("missing downlevel flags {0:?}")]
+    Downlevel(wgpu::DownlevelFlags),
+    #[error("missing features {0:?}")]
+    Features(wgpu::Features),
+    #[error("limits beyond the adapter {0:?}")]
+    Limits(Vec<(&'static str, u64, u64)>), //(name, requested, allowed)
+    #[error("buffer write {0:?}")]
+    Buffer(BufferError),
+    #[error("cursor image")]
+    Cursor(#[from] winit::window::BadImage),
+    #[error("buffer map")]
+    BufferMap(#[from] wgpu::BufferAsyncError),
+    #[error("poll")]
+    Poll(#[from] wgpu::PollError),
+    #[error("event loop")]
+    EventLoop(#[from] winit::error::EventLoopError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitReason
+{
+    CloseRequested, //close button while frame returned true
+    Programmatic, //frame returned true
+    Fatal, //Context::exit with fatal error
+    External, //event loop ended otherwise
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedrawMode
+{
+    Continuous, //redraw every frame
+    Reactive, //redraw only on window events, wakes and request_redraw
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DtSmoothing
+{
+    pub factor: f32, //weight of the newest dt in the moving average
+    pub tolerance: f32, //relative deviation from the refresh interval that still gets smoothed
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowVisibility
+{
+    AfterInit, //after graphics init
+    AfterFirstFrame, //after the first frame was rendered
+}
+
+#[derive(Clone, Debug)]
+pub struct MonitorInfo
+{
+    pub name: Option<String>,
+    pub size: (u32, u32), //physical pixels
+    pub position: (i32, i32), //physical pixels
+    pub scale_factor: f64,
+    pub refresh_rate: Option<f32>, //hz
+    pub handle: winit::monitor::MonitorHandle, //for fullscreen
+}
+
+impl MonitorInfo
+{
+    fn new(handle: winit::monitor::MonitorHandle) -> Self
+    {
+        Self
+        {
+            name: handle.name(),
+            size: handle.size().into(),
+            position: handle.position().into(),
+            scale_factor: handle.scale_factor(),
+            refresh_rate: handle.refresh_rate_millihertz().map(|mhz| mhz as f32 / 1e3),
+            handle,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fullscreen
+{
+    Borderless,
+    Exclusive { size: Option<(u32, u32)>, refresh_rate: Option<f32> }, //closest video mode of the current monitor, None keeps the native one
+}
+
 pub trait App: Sized + 'static
 {
     const BACKENDS: wgpu::Backends;
     const FEATURES: wgpu::Features;
     const LIMITS: wgpu::Limits;
+    const REQUIRED_DOWNLEVEL_FLAGS: wgpu::DownlevelFlags = wgpu::DownlevelFlags::empty();
+    const SURFACE_USAGE: wgpu::TextureUsages = wgpu::TextureUsages::RENDER_ATTACHMENT;
+    const DEPTH: Option<graphics::DepthConfig> = None; //surface sized depth buffer owned by Graphics, see Graphics::depth
+    const SAMPLE_COUNT: u32 = 1; //msaa of Frame and the ui, falls back to 1 if unsupported
+    const COLOR_SPACE: graphics::ColorSpace = graphics::ColorSpace::Srgb; //wasm canvas with the webgl2 backend only, ignored with a warning on webgpu
+    const POWER_PREFERENCE: wgpu::PowerPreference = wgpu::PowerPreference::HighPerformance; //LowPower prefers integrated gpus
+    const FORCE_FALLBACK_ADAPTER: bool = false; //software adapter, e.g. for ci
+    //picks an index into the adapters of graphics::enumerate_adapters, None or a None pick requests one by POWER_PREFERENCE,
+    //adapters are only enumerated if set
+    const SELECT_ADAPTER: Option<fn(&[wgpu::AdapterInfo]) -> Option<usize>> = None;
+    const PRESENT_MODE: wgpu::PresentMode = wgpu::PresentMode::AutoVsync; //falls back if unsupported
+    const VRR: bool = false; //low latency present mode paced to the monitor refresh rate instead of PRESENT_MODE, see Graphics::is_vrr
+    const SRGB_VIEW: bool = true; //false renders to the raw surface format, shaders have to encode srgb themselves
     #[cfg(feature = "ui")]
     const UI_DEPTH_FORMAT: Option<wgpu::TextureFormat>;
+    #[cfg(feature = "ui")]
+    const UI_ALPHA_MODE: graphics::AlphaMode = graphics::AlphaMode::Straight; //Premultiplied matches textures loaded with premultiply
+    #[cfg(feature = "ui")]
+    const UI_CAPACITY: (u64, u64) = (1, 1); //initial (vertex, index) buffer sizes
+    #[cfg(feature = "audio")]
+    const AUDIO_DUCKING: Option<audio::Ducking> = None;
+    const REDRAW_MODE: RedrawMode = RedrawMode::Continuous;
+    const WINDOW_VISIBILITY: WindowVisibility = WindowVisibility::AfterInit;
+    const RESIZABLE: bool = true; //used by the default window_attributes, see Context::set_resizable
+    const SKIP_UNREADY_FRAMES: bool = false; //no frame calls until Context::is_ready
+    const DT_SMOOTHING: Option<DtSmoothing> = None;
+    const FRAME_BUDGET_MS: Option<f32> = None; //warns about frames exceeding the budget
+    const DEBUG_OVERLAY: bool = false; //fps, backend, adapter and memory drawn over the frame, see Graphics::set_debug_overlay_region
+    const EXIT_KEY: Option<winit::keyboard::KeyCode> = None; //exits with ExitReason::CloseRequested, the key never reaches the app
+    const ERROR_ELEMENT: Option<&'static str> = None; //wasm: id of a dom element showing panics and fatal exits
+    const TRACE_PATH: Option<&'static str> = None; //wgpu trace directory, needs the trace feature, GRU_WGPU_TRACE overrides
+    const RNG_SEED: Option<u64> = None; //Context::rng_seed, random if None, GRU_WGPU_SEED overrides
+    const FIXED_FRAME_DT: Option<f32> = None; //reported dt in secs instead of the measured one for reproducible runs, GRU_WGPU_FIXED_DT overrides
+    const MAX_FPS: Option<f32> = None; //frame rate cap, e.g. with PresentMode::Immediate, see Context::set_max_fps
+    const BACKGROUND_FPS: Option<f32> = None; //frame rate cap while unfocused, occluded windows get no frames regardless
+    const FIXED_DT: Option<f32> = None; //calls fixed_update with this dt before each frame as often as the elapsed time needs
+    const MAX_FIXED_STEPS: u32 = 8; //time beyond this many steps per frame is dropped so stalls don't snowball
     type Init;
     #[cfg(feature = "ui")]
     type UiEvent;
     #[cfg(feature = "ui")]
     fn ui() -> gru_ui::Ui<'static, Self, Self::UiEvent>;
+    //title, size, decorations etc., visibility and the wasm canvas are set by the crate
+    fn window_attributes() -> winit::window::WindowAttributes { Window::default_attributes().with_resizable(Self::RESIZABLE) }
     fn init(init: Self::Init, ctx: &mut Context<Self>) -> Self;
+    fn init_failed(_: Self::Init, _: &Error) {} //graphics init failed, run returns the error afterwards
+    fn fixed_update(&mut self, _: &mut Context<Self>, _fixed_dt: f32) {} //with FIXED_DT, Context::interpolation_alpha is set for the following frame
     fn frame(&mut self, ctx: &mut Context<Self>, dt: f32) -> bool;
-    fn deinit(self, _: &mut Context<Self>) -> Option<Self::Init> { None }
+    fn graphics_recreated(&mut self, _: &mut Context<Self>) {} //rebuild gpu resources after Context::recreate_graphics
+    fn on_suspend(&mut self, _: &mut Context<Self>) {} //mobile and web backgrounding, graphics has no surface and frame is not called until on_resume
+    fn on_resume(&mut self, _: &mut Context<Self>) {} //the surface is recreated
+    fn release_gpu(&mut self, _: &mut Context<Self>) {} //drop gpu resources before Graphics goes, runs before deinit; on wasm page unload best-effort only, the signal is queued and the page may be gone before it arrives
+    fn device_lost(&mut self, _: &mut Context<Self>) {} //drop gpu resources, graphics is recreated and graphics_recreated follows
+    fn deinit(self, _: &mut Context<Self>, _: ExitReason) -> Option<Self::Init> { None }
 }
 
 pub struct Context<T: App>
@@ -71,6 +231,35 @@ pub struct Context<T: App>
     pub window: Arc<Window>,
     pub input: input::Input,
     pub graphics: graphics::Graphics,
+    waker: Waker,
+    new_graphics: Rc<RefCell<Option<Result<graphics::Graphics>>>>, //filled by recreate_graphics
+    recovering: bool, //device lost, recreation started
+    cursor: Option<winit::window::CustomCursorSource>, //applied with the event loop after the frame
+    cursor_icon: Option<CursorIcon>, //shown icon, None for a custom cursor
+    cursor_base: CursorIcon, //restored when no request comes in
+    cursor_request: Option<CursorIcon>,
+    cursor_requested: bool, //a request was applied last frame
+    windowed_size: Option<winit::dpi::PhysicalSize<u32>>, //restored when leaving fullscreen
+    redraw: bool,
+    redraw_now: bool,
+    wake_at: Option<time::Instant>,
+    real_dt: f32,
+    dt: f32,
+    accumulator: f32, //leftover secs of fixed steps
+    interpolation_alpha: f32,
+    frame_stats: stats::FrameStats,
+    profiler: Option<stats::Profiler>,
+    rng_seed: u64,
+    rng: rng::Rng,
+    fixed_frame_dt: Option<f32>,
+    smooth_dt: Option<f32>,
+    refresh_interval: Option<f32>,
+    max_fps: Option<f32>,
+    focused: bool,
+    occluded: bool,
+    visible: bool,
+    close_requested: bool,
+    exit_reason: Option<ExitReason>,
     #[cfg(not(feature = "ui"))]
     _phantom: std::marker::PhantomData<T>,
     #[cfg(feature = "ui")]
@@ -78,29 +267,66 @@ pub struct Context<T: App>
     #[cfg(feature = "ui")]
     pub ui_render: ui_render::RenderData,
     #[cfg(feature = "audio")]
-    pub audio: Option<rodio::MixerDeviceSink>,
+    pub audio: Option<audio::Audio>,
     #[cfg(feature = "storage")]
     pub storage: storage::Storage,
+    #[cfg(feature = "clipboard")]
+    clipboard: clipboard::Clipboard,
 }
 
 impl<T: App> Context<T>
 {
-    async fn init(backends: wgpu::Backends, features: wgpu::Features, limits: wgpu::Limits, window: Window) -> Self
+    async fn init(config: graphics::Config, window: Window, waker: Waker) -> Result<Self>
     {
         let window = Arc::new(window);
-        let mut graphics = graphics::Graphics::init(backends, features, limits, window.clone()).await.unwrap();
+        let mut graphics = graphics::Graphics::init(&config, window.clone()).await?;
         let size = window.inner_size().into();
         graphics.configure(size);
         let input = input::Input::new();
+        let focused = window.has_focus();
+        let refresh_interval = Self::monitor_refresh_interval(&window);
         #[cfg(feature = "ui")]
-        let (ui, ui_render) = (T::ui(), ui_render::RenderData::new(&graphics, T::UI_DEPTH_FORMAT));
+        let (ui, ui_render) = (T::ui(), ui_render::RenderData::new(&graphics, T::UI_DEPTH_FORMAT, T::UI_ALPHA_MODE, T::UI_CAPACITY));
 
-        window.set_visible(true);
-        Self
+        let rng_seed = Self::env_override("GRU_WGPU_SEED").or(T::RNG_SEED).unwrap_or_else(rng::random_seed);
+        log::info!("rng seed {rng_seed}");
+
+        let visible = T::WINDOW_VISIBILITY == WindowVisibility::AfterInit;
+        if visible { window.set_visible(true); }
+        Ok(Self
         {
             window,
             input,
             graphics,
+            waker,
+            new_graphics: Rc::new(RefCell::new(None)),
+            recovering: false,
+            cursor: None,
+            cursor_icon: Some(CursorIcon::Default),
+            cursor_base: CursorIcon::Default,
+            cursor_request: None,
+            cursor_requested: false,
+            windowed_size: None,
+            redraw: true,
+            redraw_now: false,
+            wake_at: None,
+            real_dt: 0.0,
+            dt: 0.0,
+            accumulator: 0.0,
+            interpolation_alpha: 0.0,
+            frame_stats: stats::FrameStats::default(),
+            profiler: None,
+            rng_seed,
+            rng: rng::Rng::new(rng_seed),
+            fixed_frame_dt: Self::env_override("GRU_WGPU_FIXED_DT").or(T::FIXED_FRAME_DT),
+            smooth_dt: None,
+            refresh_interval,
+            max_fps: T::MAX_FPS,
+            focused,
+            occluded: false,
+            visible,
+            close_requested: false,
+            exit_reason: None,
             #[cfg(not(feature = "ui"))]
             _phantom: std::marker::PhantomData,
             #[cfg(feature = "ui")]
@@ -111,11 +337,312 @@ impl<T: App> Context<T>
             audio: None,
             #[cfg(feature = "storage")]
             storage: storage::Storage::load(),
+            #[cfg(feature = "clipboard")]
+            clipboard: clipboard::Clipboard::new(),
+        })
+    }
+
+    fn monitor_refresh_interval(window: &Window) -> Option<f32>
+    {
+        window.current_monitor()?.refresh_rate_millihertz().map(|mhz| 1e3 / mhz as f32)
+    }
+
+    fn env_override<V: std::str::FromStr>(name: &str) -> Option<V>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::env::var(name).ok()?.parse().ok();
+        #[cfg(target_arch = "wasm32")]
+        { let _ = name; return None; }
+    }
+
+    //smoothed dt if DT_SMOOTHING is set, hitches outside the tolerance pass through unsmoothed
+    fn filter_dt(&mut self, dt: f32) -> f32
+    {
+        self.real_dt = dt;
+        let dt = self.fixed_frame_dt.unwrap_or(dt);
+        self.dt = dt;
+        let Some(smoothing) = T::DT_SMOOTHING else { return dt; };
+        let smooth = match self.smooth_dt
+        {
+            Some(smooth) =>
+            {
+                let target = self.refresh_interval.unwrap_or(smooth);
+                if (dt - target).abs() <= smoothing.tolerance * target { smooth + smoothing.factor * (dt - smooth) } else { dt }
+            },
+            None => dt,
+        };
+        self.smooth_dt = Some(smooth);
+        self.dt = smooth;
+        smooth
+    }
+
+    //rebuilds graphics for the window, App::graphics_recreated is called once it is ready
+    //native: blocks and returns init errors directly, wasm: finishes asynchronously and logs errors
+    pub fn recreate_graphics(&mut self, config: graphics::Config) -> Result<()>
+    {
+        let (window, slot, waker) = (self.window.clone(), self.new_graphics.clone(), self.waker.clone());
+        let future = async move
+        {
+            let graphics = graphics::Graphics::init(&config, window).await;
+            *slot.borrow_mut() = Some(graphics);
+            waker.wake();
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            pollster::block_on(future);
+            let failed = matches!(self.new_graphics.borrow().as_ref(), Some(Err(_)));
+            if failed
+            {
+                let Some(Err(err)) = self.new_graphics.borrow_mut().take() else { unreachable!() };
+                return Err(err);
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(future);
+        Ok(())
+    }
+
+    //swaps in recreated graphics, true if the app has to rebuild its resources
+    fn poll_graphics(&mut self) -> bool
+    {
+        let Some(graphics) = self.new_graphics.borrow_mut().take() else { return false; };
+        match graphics
+        {
+            Ok(graphics) =>
+            {
+                self.graphics = graphics;
+                self.recovering = false;
+                self.graphics.configure(self.window.inner_size().into());
+                #[cfg(feature = "ui")]
+                { self.ui_render = ui_render::RenderData::new(&self.graphics, T::UI_DEPTH_FORMAT, T::UI_ALPHA_MODE, T::UI_CAPACITY); }
+                true
+            },
+            Err(err) =>
+            {
+                log::error!("graphics recreation failed: {err}");
+                false
+            },
+        }
+    }
+
+    //unsmoothed dt of the current frame
+    pub fn real_dt(&self) -> f32 { self.real_dt }
+
+    //unsmoothed dts over the last 120 frames by default
+    pub fn frame_stats(&self) -> &stats::FrameStats { &self.frame_stats }
+    pub fn set_frame_stats(&mut self, stats: stats::FrameStats) { self.frame_stats = stats; }
+
+    //logged at init, rerun with GRU_WGPU_SEED to reproduce a session
+    pub fn rng_seed(&self) -> u64 { self.rng_seed }
+    //seeded with rng_seed, reproducible when the app draws in a deterministic order
+    pub fn rng(&mut self) -> &mut rng::Rng { &mut self.rng }
+    //Some while dt is driven by FIXED_FRAME_DT instead of the wall clock
+    pub fn fixed_frame_dt(&self) -> Option<f32> { self.fixed_frame_dt }
+
+    //records dt and cpu time of every frame until stop_profiling, restarts a running session
+    pub fn start_profiling(&mut self, path: &str) { self.profiler = Some(stats::Profiler::new(path)); }
+    //gpu time of the current frame, e.g. from the app's own timestamp queries, the column stays empty otherwise
+    pub fn profile_gpu_time(&mut self, secs: f32) { if let Some(profiler) = self.profiler.as_mut() { profiler.set_gpu_time(secs); } }
+    pub fn is_profiling(&self) -> bool { self.profiler.is_some() }
+
+    //writes the csv, on wasm as a download named after path
+    pub fn stop_profiling(&mut self) -> std::io::Result<()>
+    {
+        match self.profiler.take()
+        {
+            Some(profiler) => profiler.write(),
+            None => Ok(()),
+        }
+    }
+
+    //adds the frame dt to the accumulator and returns how many steps of fixed_dt to simulate, call once per frame, the frame loop does with App::FIXED_DT
+    pub fn fixed_steps(&mut self, fixed_dt: f32) -> u32
+    {
+        if fixed_dt <= 0.0 { return 0; }
+        self.accumulator += self.dt;
+        let steps = (self.accumulator / fixed_dt).floor();
+        self.accumulator -= steps * fixed_dt;
+        self.interpolation_alpha = (self.accumulator / fixed_dt).clamp(0.0, 1.0);
+        steps as u32
+    }
+
+    //fraction of a fixed step left after fixed_steps, for lerping between the last two simulation states
+    pub fn interpolation_alpha(&self) -> f32 { self.interpolation_alpha }
+
+    pub fn max_fps(&self) -> Option<f32> { self.max_fps }
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) { self.max_fps = max_fps.filter(|fps| *fps > 0.0); }
+
+    //min secs per frame from max_fps and vrr pacing
+    #[cfg(not(target_arch = "wasm32"))]
+    fn frame_interval(&self) -> Option<f32>
+    {
+        let vrr = self.refresh_interval.filter(|_| self.graphics.is_vrr());
+        let cap = self.fps_cap().map(|fps| 1.0 / fps);
+        vrr.into_iter().chain(cap).reduce(f32::max)
+    }
+
+    //max_fps, lowered to App::BACKGROUND_FPS while unfocused
+    fn fps_cap(&self) -> Option<f32>
+    {
+        let background = T::BACKGROUND_FPS.filter(|_| !self.focused);
+        self.max_fps.into_iter().chain(background).reduce(f32::min)
+    }
+
+    //schedules another frame in reactive mode
+    pub fn request_redraw(&mut self) { self.redraw = true; }
+    //runs the next frame directly after the current one without waiting for the event loop
+    pub fn redraw_now(&mut self) { self.redraw_now = true; }
+    //sleeps until instant and then redraws, for timers in reactive mode, the earliest request wins
+    pub fn request_wake_at(&mut self, instant: time::Instant)
+    {
+        if self.wake_at.is_none_or(|wake_at| instant < wake_at) { self.wake_at = Some(instant); }
+    }
+    pub fn waker(&self) -> Waker { self.waker.clone() }
+
+    //exits after the current frame
+    pub fn exit(&mut self, reason: ExitReason) { self.exit_reason = Some(reason); }
+    pub fn exit_reason(&self) -> Option<ExitReason> { self.exit_reason }
+
+    //wasm only knows the current monitor without the detailed monitor permission
+    pub fn monitors(&self) -> Vec<MonitorInfo> { self.window.available_monitors().map(MonitorInfo::new).collect() }
+    pub fn current_monitor(&self) -> Option<MonitorInfo> { self.window.current_monitor().map(MonitorInfo::new) }
+
+    //surface configured with a non-zero size
+    pub fn is_ready(&self) -> bool { self.graphics.surface_size().is_some() }
+
+    pub fn window_size(&self) -> (u32, u32) { self.window.inner_size().into() }
+    //true while the window was resized but the surface is not reconfigured yet
+    pub fn size_mismatch(&self) -> bool { self.graphics.surface_size() != Some(self.window_size()) }
+
+    //events of other windows are ignored
+    pub fn window_id(&self) -> winit::window::WindowId { self.window.id() }
+
+    pub fn has_focus(&self) -> bool { self.focused }
+    //fully hidden, e.g. minimized or behind other windows where the platform reports it, continuous redraws pause meanwhile
+    pub fn is_occluded(&self) -> bool { self.occluded }
+    pub fn focus_window(&self) { self.window.focus_window(); } //focuses the canvas on wasm
+
+    //disabled hittest passes clicks through the window, no-op on wasm
+    pub fn set_cursor_hittest(&self, enabled: bool)
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(err) = self.window.set_cursor_hittest(enabled) { log::warn!("cursor hittest: {err}"); }
+        #[cfg(target_arch = "wasm32")]
+        let _ = enabled;
+    }
+
+    //exclusive falls back to borderless without a current monitor or video modes (e.g. on wasm)
+    pub fn set_fullscreen(&mut self, mode: Option<Fullscreen>)
+    {
+        use winit::window::Fullscreen as WinitFullscreen;
+        if !self.is_fullscreen() && mode.is_some() { self.windowed_size = Some(self.window.inner_size()); }
+        let fullscreen = mode.map(|mode| match mode
+        {
+            Fullscreen::Borderless => WinitFullscreen::Borderless(None),
+            Fullscreen::Exclusive { size, refresh_rate } =>
+            {
+                let monitor = self.window.current_monitor();
+                let native = monitor.as_ref().map(|monitor| (monitor.size().into(), monitor.refresh_rate_millihertz().map_or(0.0, |mhz| mhz as f32 / 1e3)));
+                let (size, refresh_rate) = (size.or(native.map(|(size, _)| size)), refresh_rate.or(native.map(|(_, rate)| rate)));
+                let dist = |mode: &winit::monitor::VideoModeHandle|
+                {
+                    let (width, height): (u32, u32) = mode.size().into();
+                    let size_dist = size.map_or(0, |(w, h)| w.abs_diff(width) as u64 + h.abs_diff(height) as u64);
+                    let rate_dist = refresh_rate.map_or(0.0, |rate| (mode.refresh_rate_millihertz() as f32 / 1e3 - rate).abs());
+                    (size_dist, (rate_dist * 1e3) as u64)
+                };
+                match monitor.and_then(|monitor| monitor.video_modes().min_by_key(dist))
+                {
+                    Some(mode) => WinitFullscreen::Exclusive(mode),
+                    None =>
+                    {
+                        log::warn!("no video mode for exclusive fullscreen, using borderless");
+                        WinitFullscreen::Borderless(None)
+                    },
+                }
+            },
+        });
+        self.window.set_fullscreen(fullscreen);
+        if mode.is_none() && let Some(size) = self.windowed_size.take() { let _ = self.window.request_inner_size(size); }
+    }
+
+    pub fn is_fullscreen(&self) -> bool { self.window.fullscreen().is_some() }
+
+    //always on top/bottom, supported on windows, macos and x11, no-op on wasm
+    pub fn set_window_level(&self, level: winit::window::WindowLevel) { self.window.set_window_level(level); }
+
+    //no-op on wasm and some wayland compositors
+    pub fn set_resizable(&self, resizable: bool) { self.window.set_resizable(resizable); }
+    pub fn is_resizable(&self) -> bool { self.window.is_resizable() }
+    //minimize, maximize and close, windows and macos only, maximize also follows set_resizable on windows
+    pub fn set_enabled_buttons(&self, buttons: winit::window::WindowButtons) { self.window.set_enabled_buttons(buttons); }
+    pub fn enabled_buttons(&self) -> winit::window::WindowButtons { self.window.enabled_buttons() }
+
+    //windows only, no-op elsewhere
+    pub fn set_skip_taskbar(&self, skip: bool)
+    {
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowExtWindows;
+            self.window.set_skip_taskbar(skip);
         }
+        #[cfg(not(target_os = "windows"))]
+        let _ = skip;
     }
 
+    //persistent icon, also restored after frames without request_cursor
+    pub fn set_cursor(&mut self, icon: CursorIcon)
+    {
+        self.cursor_base = icon;
+        self.cursor_icon = Some(icon);
+        self.window.set_cursor(icon);
+    }
+
+    //icon for this frame only, e.g. from hovered widgets, the last request wins and is applied after the frame
+    pub fn request_cursor(&mut self, icon: CursorIcon) { self.cursor_request = Some(icon); }
+
+    //uses the os cursor, applied after the current frame
+    pub fn set_custom_cursor(&mut self, rgba: Vec<u8>, width: u16, height: u16, (hotspot_x, hotspot_y): (u16, u16)) -> Result<()>
+    {
+        self.cursor = Some(winit::window::CustomCursor::from_rgba(rgba, width, height, hotspot_x, hotspot_y)?);
+        Ok(())
+    }
+
+    //queued for the next frame, safe to call from within frame
+    #[cfg(feature = "ui")]
+    pub fn inject_ui_event(&mut self, event: gru_ui::event::HardwareEvent) { self.input.inject(event); }
+
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_image(&self) -> Option<clipboard::Image> { self.clipboard.image() }
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard(&self) -> &clipboard::Clipboard { &self.clipboard }
+
     #[cfg(feature = "audio")]
-    pub fn audio(&self) -> Option<&rodio::MixerDeviceSink> { self.audio.as_ref() }
+    pub fn audio(&self) -> Option<&audio::Audio> { self.audio.as_ref() }
+    #[cfg(feature = "audio")]
+    pub fn audio_mut(&mut self) -> Option<&mut audio::Audio> { self.audio.as_mut() }
+}
+
+enum Signal
+{
+    Init,
+    Wake,
+    Redraw,
+    Unload, //wasm pagehide, delivered through the event loop so not guaranteed to run
+}
+
+//opaque event type of the crate event loop
+pub struct UserEvent(Signal);
+
+//wakes the event loop for a redraw, usable from other threads
+#[derive(Clone)]
+pub struct Waker(EventLoopProxy<UserEvent>);
+
+impl Waker
+{
+    pub fn wake(&self) { self.0.send_event(UserEvent(Signal::Wake)).ok(); }
+    //renders a frame as soon as the event loop handles the event, skipping the redraw request
+    pub fn redraw_now(&self) { self.0.send_event(UserEvent(Signal::Redraw)).ok(); }
 }
 
 enum AppState<T: App>
@@ -128,50 +655,246 @@ enum AppState<T: App>
 struct AppHandler<T: App>
 {
     ctx: Option<Context<T>>,
-    event_loop_proxy: EventLoopProxy<Context<T>>,
+    init_ctx: Rc<RefCell<Option<Result<Context<T>>>>>, //filled by the async init
+    error: Option<Error>, //returned by run
+    event_loop_proxy: EventLoopProxy<UserEvent>,
     app: AppState<T>,
     then: time::Instant,
+    in_frame: bool, //re-entrancy guard of redraw
+    #[cfg(target_arch = "wasm32")]
+    pagehide: wasm_bindgen::closure::Closure<dyn FnMut()>,
 }
 
 impl<T: App> AppHandler<T>
 {
-    fn new(init: T::Init, event_loop: &EventLoop<Context<T>>) -> Self
+    fn new(init: T::Init, event_loop: &EventLoop<UserEvent>) -> Self
     {
         let event_loop_proxy = event_loop.create_proxy();
-        Self { ctx: None, event_loop_proxy, app: AppState::Init(Some(init)), then: time::now() }
+        #[cfg(target_arch = "wasm32")]
+        let pagehide =
+        {
+            use wasm_bindgen::JsCast;
+            let proxy = event_loop_proxy.clone();
+            let pagehide = wasm_bindgen::closure::Closure::<dyn FnMut()>::new(move || { proxy.send_event(UserEvent(Signal::Unload)).ok(); });
+            web_sys::window().unwrap().add_event_listener_with_callback("pagehide", pagehide.as_ref().unchecked_ref()).unwrap();
+            pagehide
+        };
+        Self
+        {
+            ctx: None,
+            init_ctx: Rc::new(RefCell::new(None)),
+            error: None,
+            event_loop_proxy,
+            app: AppState::Init(Some(init)),
+            then: time::now(),
+            in_frame: false,
+            #[cfg(target_arch = "wasm32")]
+            pagehide,
+        }
+    }
+
+    //frames requested with Context::redraw_now run right after the current one,
+    //a redraw signal arriving while a frame runs must not start a nested one
+    fn redraw(&mut self, event_loop: &ActiveEventLoop)
+    {
+        if self.in_frame { return; }
+        self.in_frame = true;
+        while self.frame(event_loop) {}
+        self.in_frame = false;
+    }
+
+    //true if another frame was requested immediately
+    fn frame(&mut self, event_loop: &ActiveEventLoop) -> bool
+    {
+        let (Some(ctx), AppState::App(app)) = (self.ctx.as_mut(), &mut self.app) else { return false; };
+        let now = time::now();
+        let dt = ctx.filter_dt(time::duration_secs(self.then, now));
+        ctx.frame_stats.push(ctx.real_dt);
+        self.then = now;
+        #[cfg(feature = "storage")]
+        ctx.storage.tick();
+        #[cfg(feature = "gamepad")]
+        ctx.input.poll_gamepads();
+        #[cfg(all(feature = "ui", feature = "clipboard"))]
+        for text in ctx.clipboard.take_pasted(ctx.input.take_paste_shortcut()) { ctx.input.paste(&text); }
+        if ctx.graphics.is_device_lost() && !ctx.recovering
+        {
+            //a failed recreation is not retried, the app can still call recreate_graphics itself
+            ctx.recovering = true;
+            app.device_lost(ctx);
+            if let Err(err) = ctx.recreate_graphics(ctx.graphics.config().clone()) { log::error!("graphics recreation failed: {err}"); }
+        }
+        if ctx.poll_graphics() { app.graphics_recreated(ctx); }
+        ctx.redraw = T::REDRAW_MODE == RedrawMode::Continuous;
+        if ctx.graphics.is_suspended() || (T::SKIP_UNREADY_FRAMES && !ctx.is_ready())
+        {
+            ctx.input.clear();
+            if ctx.redraw && !ctx.graphics.is_suspended() { ctx.window.request_redraw(); } //resumed requests one
+            return false;
+        }
+        ctx.graphics.update_debug_overlay(ctx.real_dt);
+        if let Some(fixed_dt) = T::FIXED_DT
+        {
+            let steps = ctx.fixed_steps(fixed_dt);
+            if steps > T::MAX_FIXED_STEPS { log::warn!("dropped {} fixed steps", steps - T::MAX_FIXED_STEPS); }
+            for _ in 0..steps.min(T::MAX_FIXED_STEPS) { app.fixed_update(ctx, fixed_dt); }
+        }
+        let exit = app.frame(ctx, dt);
+        if ctx.poll_graphics() { app.graphics_recreated(ctx); }
+        match ctx.cursor_request.take()
+        {
+            Some(icon) =>
+            {
+                if ctx.cursor_icon != Some(icon) { ctx.window.set_cursor(icon); }
+                ctx.cursor_icon = Some(icon);
+                ctx.cursor_requested = true;
+            },
+            None if ctx.cursor_requested =>
+            {
+                if ctx.cursor_icon != Some(ctx.cursor_base) { ctx.window.set_cursor(ctx.cursor_base); }
+                ctx.cursor_icon = Some(ctx.cursor_base);
+                ctx.cursor_requested = false;
+            },
+            None => {},
+        }
+        if let Some(cursor) = ctx.cursor.take()
+        {
+            ctx.window.set_cursor(event_loop.create_custom_cursor(cursor));
+            ctx.cursor_icon = None;
+        }
+        if !ctx.visible
+        {
+            ctx.window.set_visible(true);
+            ctx.visible = true;
+        }
+        let cpu = time::duration_secs(now, time::now());
+        //native: wait out the frame interval, vrr pacing keeps the display in its vrr window
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(interval) = ctx.frame_interval() { time::sleep_until(time::after(now, interval)); }
+        if let Some(profiler) = ctx.profiler.as_mut() { profiler.push(ctx.real_dt, cpu); }
+        if let Some(budget) = T::FRAME_BUDGET_MS
+        {
+            let frame_ms = cpu * 1e3;
+            if frame_ms > budget { log::warn!("frame took {frame_ms:.1}ms (budget {budget:.1}ms)"); }
+        }
+        if exit && ctx.exit_reason.is_none()
+        {
+            ctx.exit_reason = Some(if ctx.close_requested { ExitReason::CloseRequested } else { ExitReason::Programmatic });
+        }
+        if ctx.exit_reason.is_some() { event_loop.exit(); }
+        ctx.close_requested = false;
+        ctx.input.clear();
+        let again = std::mem::take(&mut ctx.redraw_now) && ctx.exit_reason.is_none();
+        //wasm: the browser paces redraws, a cap below the refresh rate delays the next request instead of blocking
+        #[cfg(target_arch = "wasm32")]
+        if ctx.redraw && !again && let Some(interval) = ctx.fps_cap().map(|fps| 1.0 / fps).filter(|interval| ctx.refresh_interval.is_none_or(|refresh| *interval > refresh))
+        {
+            ctx.request_wake_at(time::after(now, interval));
+            ctx.redraw = false;
+        }
+        if ctx.redraw && !again && !ctx.occluded { ctx.window.request_redraw(); }
+        again
     }
 }
 
-impl<T: App> ApplicationHandler<Context<T>> for AppHandler<T>
+impl<T: App> ApplicationHandler<UserEvent> for AppHandler<T>
 {
     fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause)
     {
         //init window & graphics
         if matches!(cause, StartCause::Init)
         {
-            let window = basics::create_window(event_loop);
+            let window = basics::create_window(event_loop, T::window_attributes());
             let proxy = self.event_loop_proxy.clone();
+            let init_ctx = self.init_ctx.clone();
             let future = async move
             {
-                let ctx = Context::init(T::BACKENDS, T::FEATURES, T::LIMITS, window).await;
-                proxy.send_event(ctx).ok().unwrap();
+                let ctx = Context::init(graphics::Config::from_app::<T>(), window, Waker(proxy.clone())).await;
+                *init_ctx.borrow_mut() = Some(ctx);
+                proxy.send_event(UserEvent(Signal::Init)).ok().unwrap();
             };
             #[cfg(not(target_arch = "wasm32"))]
             pollster::block_on(future);
             #[cfg(target_arch = "wasm32")]
             wasm_bindgen_futures::spawn_local(future);
         }
+        if let StartCause::ResumeTimeReached { .. } = cause && let Some(ctx) = self.ctx.as_mut()
+            && ctx.wake_at.is_some_and(|wake_at| wake_at <= time::now())
+        {
+            ctx.wake_at = None;
+            ctx.window.request_redraw();
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop)
+    {
+        let Some(ctx) = self.ctx.as_ref() else { return; };
+        event_loop.set_control_flow(match ctx.wake_at
+        {
+            Some(wake_at) => ControlFlow::WaitUntil(time::to_winit(wake_at)),
+            None => ControlFlow::Wait,
+        });
+    }
+
+    //also sent once at startup before init finished, ctx is None then
+    fn resumed(&mut self, _: &ActiveEventLoop)
+    {
+        let (Some(ctx), AppState::App(app)) = (self.ctx.as_mut(), &mut self.app) else { return; };
+        if !ctx.graphics.is_suspended() { return; }
+        if let Err(err) = ctx.graphics.resume(ctx.window.clone())
+        {
+            log::error!("surface recreation failed: {err}");
+            return;
+        }
+        app.on_resume(ctx);
+        ctx.window.request_redraw();
     }
 
-    fn resumed(&mut self, _: &winit::event_loop::ActiveEventLoop) {}
+    fn suspended(&mut self, _: &ActiveEventLoop)
+    {
+        let (Some(ctx), AppState::App(app)) = (self.ctx.as_mut(), &mut self.app) else { return; };
+        app.on_suspend(ctx);
+        ctx.graphics.suspend();
+    }
 
-    fn user_event(&mut self, _: &ActiveEventLoop, mut ctx: Context<T>)
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent)
     {
-        let AppState::Init(init) = &mut self.app else { unreachable!() };
-        let init = init.take().unwrap();
-        let app = T::init(init, &mut ctx);
-        self.app = AppState::App(app);
-        self.ctx = Some(ctx);
+        match event.0
+        {
+            Signal::Init =>
+            {
+                let ctx = self.init_ctx.borrow_mut().take().unwrap();
+                let AppState::Init(init) = &mut self.app else { unreachable!() };
+                let init = init.take().unwrap();
+                let mut ctx = match ctx
+                {
+                    Ok(ctx) => ctx,
+                    Err(err) =>
+                    {
+                        log::error!("init failed: {err}");
+                        #[cfg(target_arch = "wasm32")]
+                        if let Some(id) = T::ERROR_ELEMENT { basics::report_error(id, &err.to_string()); }
+                        T::init_failed(init, &err);
+                        self.app = AppState::Deinit;
+                        self.error = Some(err);
+                        event_loop.exit();
+                        return;
+                    },
+                };
+                let app = T::init(init, &mut ctx);
+                self.app = AppState::App(app);
+                ctx.window.request_redraw();
+                self.ctx = Some(ctx);
+            },
+            Signal::Wake => if let Some(ctx) = self.ctx.as_ref() { ctx.window.request_redraw(); },
+            Signal::Redraw => if self.ctx.as_ref().is_some_and(|ctx| ctx.exit_reason.is_none()) { self.redraw(event_loop); },
+            //exiting does the teardown
+            Signal::Unload => if let Some(ctx) = self.ctx.as_mut()
+            {
+                ctx.exit_reason.get_or_insert(ExitReason::External);
+                event_loop.exit();
+            },
+        }
     }
 
     fn device_event(&mut self, _: &ActiveEventLoop, _: winit::event::DeviceId, event: winit::event::DeviceEvent)
@@ -182,65 +905,115 @@ impl<T: App> ApplicationHandler<Context<T>> for AppHandler<T>
         }
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: winit::window::WindowId, event: WindowEvent)
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: winit::window::WindowId, event: WindowEvent)
     {
-        if let Some(ctx) = self.ctx.as_mut()
+        if let Some(ctx) = self.ctx.as_mut() && window_id == ctx.window.id()
         {
+            if T::REDRAW_MODE == RedrawMode::Reactive && !matches!(event, WindowEvent::RedrawRequested) { ctx.window.request_redraw(); }
+            if let (Some(exit_key), WindowEvent::KeyboardInput { event: key_event, .. }) = (T::EXIT_KEY, &event)
+                && key_event.physical_key == winit::keyboard::PhysicalKey::Code(exit_key)
+            {
+                if key_event.state.is_pressed() && ctx.exit_reason.is_none()
+                {
+                    ctx.exit_reason = Some(ExitReason::CloseRequested);
+                    event_loop.exit();
+                }
+                return;
+            }
             #[cfg(feature = "audio")]
             if ctx.audio.is_none() && matches!(event, WindowEvent::MouseInput { .. })
             {
                 let mut sink = rodio::DeviceSinkBuilder::open_default_sink().unwrap();
                 sink.log_on_drop(false);
-                ctx.audio = Some(sink);
+                ctx.audio = Some(audio::Audio::new(sink, T::AUDIO_DUCKING));
+            }
+            if let WindowEvent::Focused(focused) = event
+            {
+                ctx.focused = focused;
+                #[cfg(feature = "audio")]
+                if let Some(audio) = ctx.audio.as_mut() { audio.set_focused(focused); }
+            }
+            if let WindowEvent::Occluded(occluded) = event
+            {
+                ctx.occluded = occluded;
+                if !occluded { ctx.window.request_redraw(); }
             }
             match event
             {
+                WindowEvent::Moved(_) =>
+                {
+                    ctx.refresh_interval = Context::<T>::monitor_refresh_interval(&ctx.window);
+                    ctx.input.event(input::RawEvent::Window(event));
+                },
                 WindowEvent::Resized(new_size) => 
                 {
                     let width = new_size.width.max(1);
                     let height = new_size.height.max(1);
                     ctx.graphics.configure((width, height));
                 },
-                WindowEvent::RedrawRequested => //frame
+                WindowEvent::RedrawRequested => self.redraw(event_loop), //frame
+                event =>
                 {
-                    let now = time::now();
-                    let dt = time::duration_secs(self.then, now);
-                    self.then = now;
-                    let AppState::App(app) = &mut self.app else { unreachable!() };
-                    if app.frame(ctx, dt) { event_loop.exit(); }
-                    ctx.input.clear();
-                    ctx.window.request_redraw();
+                    if matches!(event, WindowEvent::CloseRequested) { ctx.close_requested = true; }
+                    ctx.input.event(input::RawEvent::Window(event));
                 },
-                event => ctx.input.event(input::RawEvent::Window(event)),
             }
         }
     }
 
     fn exiting(&mut self, _: &ActiveEventLoop)
     {
-        let mut ctx = self.ctx.take().unwrap();
+        #[cfg(target_arch = "wasm32")]
+        {
+            use wasm_bindgen::JsCast;
+            web_sys::window().unwrap().remove_event_listener_with_callback("pagehide", self.pagehide.as_ref().unchecked_ref()).ok();
+        }
+        let Some(mut ctx) = self.ctx.take() else { return; }; //init failed
         let mut app = AppState::Deinit;
         std::mem::swap(&mut self.app, &mut app);
-        let AppState::App(app) = app else { unreachable!() };
-        let init = app.deinit(&mut ctx);
+        let AppState::App(mut app) = app else { unreachable!() };
+        app.release_gpu(&mut ctx);
+        let reason = ctx.exit_reason.unwrap_or(ExitReason::External);
+        #[cfg(target_arch = "wasm32")]
+        if let (ExitReason::Fatal, Some(id)) = (reason, T::ERROR_ELEMENT) { basics::report_error(id, "The application stopped after a fatal error."); }
+        let init = app.deinit(&mut ctx, reason);
         drop(ctx);
         drop(init);
     }
 }
 
-pub fn run<T: App>(init: T::Init)
+//the event loop run uses, winit requires the main thread unless any_thread is set,
+//which is only supported on windows and linux (x11 and wayland), elsewhere it panics off the main thread
+pub fn event_loop(any_thread: bool) -> Result<EventLoop<UserEvent>>
 {
-    basics::init_logging();
-    
+    #[allow(unused_mut)]
+    let mut builder = EventLoop::with_user_event();
     #[cfg(target_os = "linux")]
-    let event_loop =
     {
         use winit::platform::x11::EventLoopBuilderExtX11;
-        EventLoop::with_user_event().with_x11().build().unwrap()
-    };
-    #[cfg(not(target_os = "linux"))]
-    let event_loop = EventLoop::with_user_event().build().unwrap();
+        builder.with_x11().with_any_thread(any_thread);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use winit::platform::windows::EventLoopBuilderExtWindows;
+        builder.with_any_thread(any_thread);
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    if any_thread { log::warn!("any_thread event loops are unsupported on this platform"); }
+    Ok(builder.build()?)
+}
 
+//for embedders controlling the threading, see event_loop
+pub fn run_on<T: App>(event_loop: EventLoop<UserEvent>, init: T::Init) -> Result<()>
+{
+    basics::init_logging(T::ERROR_ELEMENT);
     let mut app: AppHandler<T> = AppHandler::new(init, &event_loop);
-    event_loop.run_app(&mut app).unwrap();
+    event_loop.run_app(&mut app)?;
+    app.error.map_or(Ok(()), Err)
+}
+
+//errors of event loop creation and graphics init
+pub fn run<T: App>(init: T::Init) -> Result<()>
+{
+    run_on::<T>(event_loop(false)?, init)
 }
@@ -0,0 +1,46 @@
+//splitmix64, small and reproducible across platforms, not for cryptography
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng
+{
+    pub fn new(seed: u64) -> Self { Self(seed) }
+
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u32(&mut self) -> u32 { (self.next_u64() >> 32) as u32 }
+    //in 0..1
+    pub fn next_f32(&mut self) -> f32 { (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 }
+    pub fn range_f32(&mut self, min: f32, max: f32) -> f32 { min + (max - min) * self.next_f32() }
+
+    //in min..max, max exclusive
+    pub fn range(&mut self, min: u64, max: u64) -> u64
+    {
+        if max <= min { return min; }
+        min + self.next_u64() % (max - min)
+    }
+
+    pub fn bool(&mut self, p: f32) -> bool { self.next_f32() < p }
+}
+
+//differs per call and run
+pub fn random_seed() -> u64
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::hash::{BuildHasher, Hasher};
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        hasher.write_u32(std::process::id());
+        return hasher.finish();
+    }
+    #[cfg(target_arch = "wasm32")]
+    return ((js_sys::Math::random() * 2f64.powi(32)) as u64) << 32 | (js_sys::Math::random() * 2f64.powi(32)) as u64;
+}
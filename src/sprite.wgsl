@@ -0,0 +1,37 @@
+struct Instance
+{
+    @location(0) pos: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) uv: vec4<f32>,
+    @location(3) col: vec4<f32>,
+    @location(4) layer: u32,
+};
+
+struct VSOutput
+{
+    @builtin(position) pos: vec4<f32>,
+    @location(0) col: vec4<f32>,
+    @location(1) coords: vec2<f32>,
+    @location(2) @interpolate(flat) layer: u32,
+}
+
+//two counter-clockwise triangles per instance, corners in 0..1 with y down
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, in: Instance) -> VSOutput
+{
+    var corners = array<vec2<f32>, 6>(vec2(0.0, 0.0), vec2(0.0, 1.0), vec2(1.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0), vec2(1.0, 1.0));
+    var corner = corners[index];
+    var pos = in.pos + corner * in.size;
+    return VSOutput(vec4<f32>(pos, 0.0, 1.0), in.col, mix(in.uv.xy, in.uv.zw, corner), in.layer);
+}
+
+@group(0) @binding(0)
+var t_sprites: texture_2d_array<f32>;
+@group(0) @binding(1)
+var s_sprites: sampler;
+
+@fragment
+fn fs_main(in: VSOutput) -> @location(0) vec4<f32>
+{
+    return textureSample(t_sprites, s_sprites, in.coords, in.layer) * in.col;
+}
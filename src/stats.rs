@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatsWindow
+{
+    Frames(usize),
+    Secs(f32),
+}
+
+//rolling frame times in secs
+#[derive(Clone, Debug)]
+pub struct FrameStats
+{
+    window: StatsWindow,
+    frames: VecDeque<f32>,
+    sum: f32,
+}
+
+impl FrameStats
+{
+    pub fn new(window: StatsWindow) -> Self
+    {
+        Self { window, frames: VecDeque::new(), sum: 0.0 }
+    }
+
+    pub(crate) fn push(&mut self, dt: f32)
+    {
+        self.frames.push_back(dt);
+        self.sum += dt;
+        loop
+        {
+            let full = match self.window
+            {
+                StatsWindow::Frames(frames) => self.frames.len() > frames.max(1),
+                StatsWindow::Secs(secs) => self.frames.len() > 1 && self.sum - self.frames[0] >= secs,
+            };
+            if !full { break; }
+            self.sum -= self.frames.pop_front().unwrap();
+        }
+    }
+
+    pub fn window(&self) -> StatsWindow { self.window }
+    pub fn frames(&self) -> usize { self.frames.len() }
+    pub fn fps(&self) -> f32 { if self.sum > 0.0 { self.frames.len() as f32 / self.sum } else { 0.0 } }
+    pub fn avg_frame_time(&self) -> f32 { if self.frames.is_empty() { 0.0 } else { self.sum / self.frames.len() as f32 } }
+    pub fn min_frame_time(&self) -> f32 { self.frames.iter().copied().reduce(f32::min).unwrap_or(0.0) }
+    pub fn max_frame_time(&self) -> f32 { self.frames.iter().copied().reduce(f32::max).unwrap_or(0.0) }
+
+    //frame time that p of the frames stay below, e.g. 0.99
+    pub fn percentile(&self, p: f32) -> f32
+    {
+        if self.frames.is_empty() { return 0.0; }
+        let mut sorted: Vec<_> = self.frames.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        sorted[((sorted.len() - 1) as f32 * p.clamp(0.0, 1.0)).round() as usize]
+    }
+    pub fn p99_frame_time(&self) -> f32 { self.percentile(0.99) }
+}
+
+impl Default for FrameStats
+{
+    fn default() -> Self { Self::new(StatsWindow::Frames(120)) }
+}
+
+//per frame rows for Context::start_profiling
+#[derive(Clone, Debug)]
+pub(crate) struct Profiler
+{
+    path: String,
+    rows: Vec<(f32, f32, Option<f32>)>, //dt, cpu and gpu time in secs
+    gpu: Option<f32>,
+}
+
+impl Profiler
+{
+    pub(crate) fn new(path: &str) -> Self
+    {
+        Self { path: path.to_string(), rows: Vec::new(), gpu: None }
+    }
+
+    pub(crate) fn set_gpu_time(&mut self, secs: f32) { self.gpu = Some(secs); }
+
+    pub(crate) fn push(&mut self, dt: f32, cpu: f32)
+    {
+        self.rows.push((dt, cpu, self.gpu.take()));
+    }
+
+    fn to_csv(&self) -> String
+    {
+        let mut csv = String::from("frame,dt_ms,cpu_ms,gpu_ms\n");
+        for (frame, (dt, cpu, gpu)) in self.rows.iter().enumerate()
+        {
+            let gpu = gpu.map(|gpu| format!("{:.3}", gpu * 1e3)).unwrap_or_default();
+            csv += &format!("{frame},{:.3},{:.3},{gpu}\n", dt * 1e3, cpu * 1e3);
+        }
+        csv
+    }
+
+    //native: writes the file, wasm: downloads it under the file name of path
+    pub(crate) fn write(&self) -> std::io::Result<()>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::fs::write(&self.path, self.to_csv());
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            download(&self.path, &self.to_csv());
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(path: &str, data: &str)
+{
+    use wasm_bindgen::JsCast;
+    let parts = js_sys::Array::of1(&data.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/csv");
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options) else { return; };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else { return; };
+    if let Some(anchor) = web_sys::window().and_then(|window| window.document()).and_then(|document| document.create_element("a").ok())
+    {
+        let anchor: web_sys::HtmlAnchorElement = anchor.unchecked_into();
+        anchor.set_href(&url);
+        anchor.set_download(path.rsplit(['/', '\\']).next().unwrap_or(path));
+        anchor.click();
+    }
+    web_sys::Url::revoke_object_url(&url).ok();
+}
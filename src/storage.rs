@@ -1,12 +1,38 @@
 #[cfg(not(target_arch = "wasm32"))]
 const PATH: &str = "CACHE.gru";
 
+const INSTALL_ID_KEY: &str = "gru.install_id";
+
+#[cfg(target_arch = "wasm32")]
+type Pending = std::rc::Rc<std::cell::RefCell<std::collections::HashMap<String, Option<String>>>>;
+
+#[cfg(target_arch = "wasm32")]
+struct Coalesce
+{
+    interval: f32,
+    last_flush: crate::time::Instant,
+    pending: Pending,
+    pagehide: wasm_bindgen::closure::Closure<dyn FnMut()>,
+}
+
 pub struct Storage
 {
     #[cfg(not(target_arch = "wasm32"))]
     data: ahash::AHashMap<String, String>,
     #[cfg(target_arch = "wasm32")]
     data: web_sys::Storage,
+    #[cfg(target_arch = "wasm32")]
+    coalesce: Option<Coalesce>,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_pending(data: &web_sys::Storage, pending: &Pending)
+{
+    for (key, value) in pending.borrow_mut().drain()
+    {
+        if let Some(value) = value { data.set_item(&key, &value).unwrap(); }
+        else { data.remove_item(&key).unwrap(); }
+    }
 }
 
 impl Storage
@@ -19,6 +45,57 @@ impl Storage
             data: std::fs::read(PATH).map(|contents| bincode::deserialize(&contents).unwrap()).unwrap_or_else(|_| ahash::AHashMap::new()),
             #[cfg(target_arch = "wasm32")]
             data: web_sys::window().unwrap().local_storage().unwrap().unwrap(),
+            #[cfg(target_arch = "wasm32")]
+            coalesce: None,
+        }
+    }
+
+    //wasm: buffer sets in memory and write them to localStorage every interval secs and on pagehide
+    //native: no-op, data is always in memory and written on drop
+    pub fn set_write_coalescing(&mut self, interval: Option<f32>)
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = interval;
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            use wasm_bindgen::JsCast;
+            self.flush();
+            let window = web_sys::window().unwrap();
+            if let Some(coalesce) = self.coalesce.take()
+            {
+                window.remove_event_listener_with_callback("pagehide", coalesce.pagehide.as_ref().unchecked_ref()).unwrap();
+            }
+            if let Some(interval) = interval
+            {
+                let pending = Pending::default();
+                let (data, listener_pending) = (self.data.clone(), pending.clone());
+                let pagehide = wasm_bindgen::closure::Closure::<dyn FnMut()>::new(move || write_pending(&data, &listener_pending));
+                window.add_event_listener_with_callback("pagehide", pagehide.as_ref().unchecked_ref()).unwrap();
+                self.coalesce = Some(Coalesce { interval, last_flush: crate::time::now(), pending, pagehide });
+            }
+        }
+    }
+
+    pub fn flush(&mut self)
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        std::fs::write(PATH, bincode::serialize(&self.data).unwrap()).unwrap();
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(coalesce) = self.coalesce.as_mut()
+        {
+            write_pending(&self.data, &coalesce.pending);
+            coalesce.last_flush = crate::time::now();
+        }
+    }
+
+    pub(crate) fn tick(&mut self)
+    {
+        #[cfg(target_arch = "wasm32")]
+        if self.coalesce.as_ref().is_some_and(|coalesce| crate::time::duration_secs(coalesce.last_flush, crate::time::now()) >= coalesce.interval)
+        {
+            self.flush();
         }
     }
 
@@ -29,7 +106,8 @@ impl Storage
         else { self.data.remove(key); }
 
         #[cfg(target_arch = "wasm32")]
-        if let Some(value) = value { self.data.set_item(key, value).unwrap(); }
+        if let Some(coalesce) = self.coalesce.as_ref() { coalesce.pending.borrow_mut().insert(key.to_string(), value.map(str::to_string)); }
+        else if let Some(value) = value { self.data.set_item(key, value).unwrap(); }
         else { self.data.remove_item(key).unwrap(); }
     }
 
@@ -39,7 +117,11 @@ impl Storage
         return self.data.get(key).map(|value| value.to_string());
 
         #[cfg(target_arch = "wasm32")]
-        return self.data.get_item(key).unwrap();
+        return match self.coalesce.as_ref().and_then(|coalesce| coalesce.pending.borrow().get(key).cloned())
+        {
+            Some(value) => value,
+            None => self.data.get_item(key).unwrap(),
+        };
     }
 
     pub fn clear(&mut self)
@@ -48,7 +130,10 @@ impl Storage
         self.data.clear();
 
         #[cfg(target_arch = "wasm32")]
-        self.data.clear().unwrap();
+        {
+            if let Some(coalesce) = self.coalesce.as_ref() { coalesce.pending.borrow_mut().clear(); }
+            self.data.clear().unwrap();
+        }
     }
 
     pub fn keys(&self) -> Vec<String>
@@ -57,7 +142,34 @@ impl Storage
         return self.data.keys().cloned().collect();
 
         #[cfg(target_arch = "wasm32")]
-        return (0..self.data.length().unwrap()).map(|i| self.data.key(i).unwrap().unwrap()).collect();
+        {
+            let mut keys: Vec<String> = (0..self.data.length().unwrap()).map(|i| self.data.key(i).unwrap().unwrap()).collect();
+            if let Some(coalesce) = self.coalesce.as_ref()
+            {
+                let pending = coalesce.pending.borrow();
+                keys.retain(|key| !matches!(pending.get(key), Some(None)));
+                keys.extend(pending.iter().filter(|(key, value)| value.is_some() && !keys.contains(key)).map(|(key, _)| key.clone()).collect::<Vec<_>>());
+            }
+            return keys;
+        }
+    }
+
+    pub fn keys_with_prefix(&self, prefix: &str) -> Vec<String>
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.data.keys().filter(|key| key.starts_with(prefix)).cloned().collect();
+
+        #[cfg(target_arch = "wasm32")]
+        return self.keys().into_iter().filter(|key| key.starts_with(prefix)).collect();
+    }
+
+    pub fn clear_prefix(&mut self, prefix: &str)
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.data.retain(|key, _| !key.starts_with(prefix));
+
+        #[cfg(target_arch = "wasm32")]
+        for key in self.keys_with_prefix(prefix) { self.set(&key, None); }
     }
 }
 
@@ -66,6 +178,24 @@ impl Drop for Storage
 {
     fn drop(&mut self)
     {
-        std::fs::write(PATH, bincode::serialize(&self.data).unwrap()).unwrap();
+        self.flush();
     }
 }
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for Storage
+{
+    fn drop(&mut self)
+    {
+        self.set_write_coalescing(None);
+    }
+}
+
+//random on first call, then persisted under gru.install_id
+pub fn install_id(storage: &mut Storage) -> u64
+{
+    if let Some(id) = storage.get(INSTALL_ID_KEY).and_then(|id| id.parse().ok()) { return id; }
+    let id = crate::rng::random_seed();
+    storage.set(INSTALL_ID_KEY, Some(&id.to_string()));
+    id
+}
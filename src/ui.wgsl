@@ -21,18 +21,57 @@ fn vs_main(in: VSInput) -> VSOutput
 }
 
 @group(0) @binding(0)
-var t_glyphs: texture_2d_array<f32>;
+var t_atlas0: texture_2d_array<f32>;
 @group(0) @binding(1)
+var t_atlas1: texture_2d_array<f32>;
+@group(0) @binding(2)
+var t_atlas2: texture_2d_array<f32>;
+@group(0) @binding(3)
+var t_atlas3: texture_2d_array<f32>;
+@group(0) @binding(4)
 var s_glyphs: sampler;
+@group(0) @binding(5)
+var s_glyphs_nearest: sampler;
+
+struct Glyph
+{
+    atlas: i32,
+    layer: i32,
+    nearest: bool,
+    ddx: vec2<f32>,
+    ddy: vec2<f32>,
+}
+
+//explicit gradients allow sampling in non-uniform control flow
+fn sample_dist(uv: vec2<f32>, g: Glyph) -> f32
+{
+    if g.nearest
+    {
+        switch g.atlas
+        {
+            case 1: { return textureSampleGrad(t_atlas1, s_glyphs_nearest, uv, g.layer, g.ddx, g.ddy).r; }
+            case 2: { return textureSampleGrad(t_atlas2, s_glyphs_nearest, uv, g.layer, g.ddx, g.ddy).r; }
+            case 3: { return textureSampleGrad(t_atlas3, s_glyphs_nearest, uv, g.layer, g.ddx, g.ddy).r; }
+            default: { return textureSampleGrad(t_atlas0, s_glyphs_nearest, uv, g.layer, g.ddx, g.ddy).r; }
+        }
+    }
+    switch g.atlas
+    {
+        case 1: { return textureSampleGrad(t_atlas1, s_glyphs, uv, g.layer, g.ddx, g.ddy).r; }
+        case 2: { return textureSampleGrad(t_atlas2, s_glyphs, uv, g.layer, g.ddx, g.ddy).r; }
+        case 3: { return textureSampleGrad(t_atlas3, s_glyphs, uv, g.layer, g.ddx, g.ddy).r; }
+        default: { return textureSampleGrad(t_atlas0, s_glyphs, uv, g.layer, g.ddx, g.ddy).r; }
+    }
+}
 
 fn contour(d: f32, w: f32) -> f32
 {
     return smoothstep(0.5 - w, 0.5 + w, d);
 }
 
-fn samp(uv: vec2<f32>, layer: i32, w: f32) -> f32
+fn samp(uv: vec2<f32>, g: Glyph, w: f32) -> f32
 {
-    return contour(textureSample(t_glyphs, s_glyphs, uv, layer).r, w);
+    return contour(sample_dist(uv, g), w);
 }
 
 fn srgb2rgb(srgb: f32) -> f32
@@ -41,25 +80,44 @@ fn srgb2rgb(srgb: f32) -> f32
     else { return pow((srgb + 0.055) / 1.055, 2.4); }
 }
 
+fn rgb2srgb(rgb: f32) -> f32
+{
+    if rgb <= 0.0031308 { return rgb * 12.92; }
+    else { return 1.055 * pow(rgb, 1.0 / 2.4) - 0.055; }
+}
+
+//set when rendering to a non-srgb view
+override ENCODE_SRGB: bool = false;
+override PREMULTIPLY: bool = false;
+
+fn output(straight: vec4<f32>) -> vec4<f32>
+{
+    var col = straight;
+    if PREMULTIPLY { col = vec4<f32>(col.rgb * col.a, col.a); }
+    if ENCODE_SRGB { return vec4<f32>(rgb2srgb(col.r), rgb2srgb(col.g), rgb2srgb(col.b), col.a); }
+    else { return col; }
+}
+
 @fragment
 fn fs_main(in: VSOutput) -> @location(0) vec4<f32>
 {
-    var layer = max(in.layer, 0);
+    var packed = max(in.layer, 0);
     var uv = in.coords;
-    var dist: f32 = textureSample(t_glyphs, s_glyphs, uv, layer).r;
+    var g = Glyph((packed >> 16u) & 0x3fff, packed & 0xffff, (packed >> 30u) == 1, dpdx(uv), dpdy(uv));
+    var dist: f32 = sample_dist(uv, g);
     var width = fwidth(dist);
     var alpha = contour(dist, width);
     var dscale = 0.354; //half of 1/sqrt2
-    var duv = dscale * (dpdx(uv) + dpdy(uv));
+    var duv = dscale * (g.ddx + g.ddy);
     var box = vec4<f32>(uv - duv, uv + duv);
     var asum =
-        samp(box.xy, layer, width)
-        + samp(box.zw, layer, width)
-        + samp(box.xw, layer, width)
-        + samp(box.zy, layer, width);
+        samp(box.xy, g, width)
+        + samp(box.zw, g, width)
+        + samp(box.xw, g, width)
+        + samp(box.zy, g, width);
     alpha = (alpha + 0.5 * asum) / 3.0;
     alpha = 1.0 - srgb2rgb(1.0 - alpha);
 
-    if in.layer == -1 { return in.col; }
-    else { return vec4<f32>(in.col.rgb, alpha * in.col.a); }
+    if in.layer == -1 { return output(in.col); }
+    else { return output(vec4<f32>(in.col.rgb, alpha * in.col.a)); }
 }
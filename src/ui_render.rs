@@ -1,8 +1,10 @@
-use super::graphics::Graphics;
+use super::{Error, BufferError, Result, graphics::{Graphics, AlphaMode, coords}};
 use winit::window::Window;
 use gru_misc::math::*;
 
 const SHADER: wgpu::ShaderModuleDescriptor<'static> = wgpu::include_wgsl!("ui.wgsl");
+pub const SHADER_SOURCE: &str = include_str!("ui.wgsl"); //starting point for custom vertex shaders
+const MAX_ATLASES: usize = 4; //must match ui.wgsl
 
 #[repr(C, packed)]
 struct Vertex
@@ -13,8 +15,21 @@ struct Vertex
     layer: i32,
 }
 
+//extra per-vertex data appended after the builtin attributes (locations 0..=3)
+pub struct CustomVertex
+{
+    pub shader: wgpu::ShaderModuleDescriptor<'static>, //vs_main and fs_main with the builtin and the extra inputs, masks layer like ui.wgsl, declares ENCODE_SRGB (and PREMULTIPLY for AlphaMode::Premultiplied) like ui.wgsl
+    pub attributes: Vec<wgpu::VertexAttribute>, //locations from 4, offsets relative to the extra data
+    pub size: u64, //extra bytes per vertex, multiple of 4
+    pub fill: Box<dyn FnMut(&gru_ui::paint::Frame, usize, &mut [u8])>, //writes the extra bytes of vertex i
+}
+
 pub struct RenderData
 {
+    depth_format: Option<wgpu::TextureFormat>,
+    alpha_mode: AlphaMode,
+    custom: Option<CustomVertex>,
+    upload: bool, //forces an upload after the layout changed
     bind_group_layout: wgpu::BindGroupLayout,
     render_pipeline: wgpu::RenderPipeline,
     vertex_buf: wgpu::Buffer,
@@ -23,9 +38,10 @@ pub struct RenderData
     len_indices: u64, //u16 count
     num_indices: u32, //index count to render
     glyphs_version: Option<u64>,
-    glyphs: wgpu::Texture,
-    glyphs_view: wgpu::TextureView,
-    glyphs_sampler: wgpu::Sampler,
+    atlases: Vec<(wgpu::Texture, wgpu::TextureView)>, //one texture array per atlas size
+    layer_map: Vec<i32>, //font layer -> nearest << 30 | atlas << 16 | atlas layer
+    nearest_layers: Vec<u32>, //font layers sampled with the nearest sampler
+    glyphs_samplers: [wgpu::Sampler; 2], //linear, nearest
     bind_group: wgpu::BindGroup,
 }
 
@@ -61,33 +77,36 @@ impl RenderData
         }
     }
     
-    fn create_pipeline(device: &wgpu::Device, view_format: wgpu::TextureFormat, depth_format: Option<wgpu::TextureFormat>) -> (wgpu::BindGroupLayout, wgpu::RenderPipeline)
+    fn stride(custom: Option<&CustomVertex>) -> u64
+    {
+        std::mem::size_of::<Vertex>() as u64 + custom.map_or(0, |custom| custom.size)
+    }
+
+    fn create_pipeline(device: &wgpu::Device, view_format: wgpu::TextureFormat, sample_count: u32, depth_format: Option<wgpu::TextureFormat>, alpha_mode: AlphaMode, custom: Option<&CustomVertex>) -> (wgpu::BindGroupLayout, wgpu::RenderPipeline)
     {
+        let mut entries: Vec<_> = (0..MAX_ATLASES as u32).map(|binding| wgpu::BindGroupLayoutEntry
+        {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture
+            {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+                multisampled: false,
+            },
+            count: None,
+        }).collect();
+        entries.extend((0..2).map(|i| wgpu::BindGroupLayoutEntry
+        {
+            binding: MAX_ATLASES as u32 + i,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        }));
         let bind_group_layout_descriptor_descr = wgpu::BindGroupLayoutDescriptor
         {
             label: None,
-            entries:
-            &[
-                wgpu::BindGroupLayoutEntry
-                {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture
-                    {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2Array,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry
-                {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ]
+            entries: &entries,
         };
         let bind_group_layout = device.create_bind_group_layout(&bind_group_layout_descriptor_descr);
 
@@ -99,32 +118,49 @@ impl RenderData
         };
         let pipeline_layout = device.create_pipeline_layout(&pipeline_layout_descr);
 
-        let ui_shader = device.create_shader_module(SHADER);
+        let ui_shader = device.create_shader_module(custom.map_or(SHADER, |custom| custom.shader.clone()));
+        let mut attributes = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4, 2 => Float32x2, 3 => Sint32].to_vec();
+        if let Some(custom) = custom
+        {
+            let offset = std::mem::size_of::<Vertex>() as u64;
+            attributes.extend(custom.attributes.iter().map(|&attribute| wgpu::VertexAttribute { offset: offset + attribute.offset, ..attribute }));
+        }
         let color_target_state = wgpu::ColorTargetState
         {
             format: view_format,
-            blend: Some(wgpu::BlendState
+            blend: Some(match alpha_mode
             {
-                color: wgpu::BlendComponent
+                AlphaMode::Straight => wgpu::BlendState
                 {
-                    src_factor: wgpu::BlendFactor::SrcAlpha,
-                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    operation: wgpu::BlendOperation::Add,
-                },
-                alpha: wgpu::BlendComponent
-                {
-                    src_factor: wgpu::BlendFactor::SrcAlpha,
-                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                    operation: wgpu::BlendOperation::Add,
+                    color: wgpu::BlendComponent
+                    {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent
+                    {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
                 },
+                AlphaMode::Premultiplied => alpha_mode.blend_state(),
             }),
             write_mask: wgpu::ColorWrites::ALL,
         };
         let color_target_state = Some(color_target_state);
+        //PREMULTIPLY is only passed when used so straight custom shaders need not declare it
+        let encode_srgb = ("ENCODE_SRGB", if view_format.is_srgb() { 0.0 } else { 1.0 });
+        let constants = match alpha_mode
+        {
+            AlphaMode::Straight => vec![encode_srgb],
+            AlphaMode::Premultiplied => vec![encode_srgb, ("PREMULTIPLY", 1.0)],
+        };
 
         let render_pipeline_descr = wgpu::RenderPipelineDescriptor
         {
-            label: None,
+            label: Some("gru ui"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState
             {
@@ -132,9 +168,9 @@ impl RenderData
                 entry_point: Some("vs_main"),
                 buffers: &[wgpu::VertexBufferLayout
                 {
-                    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                    array_stride: Self::stride(custom),
                     step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4, 2 => Float32x2, 3 => Sint32]
+                    attributes: &attributes
                 }],
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             },
@@ -142,7 +178,7 @@ impl RenderData
             {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
+                front_face: crate::graphics::FRONT_FACE,
                 cull_mode: Some(wgpu::Face::Back),
                 unclipped_depth: false,
                 polygon_mode: wgpu::PolygonMode::Fill,
@@ -151,7 +187,7 @@ impl RenderData
             depth_stencil: depth_format.map(Self::depth_stencil),
             multisample: wgpu::MultisampleState
             {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -160,7 +196,11 @@ impl RenderData
                 module: &ui_shader,
                 entry_point: Some("fs_main"),
                 targets: std::slice::from_ref(&color_target_state),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                compilation_options: wgpu::PipelineCompilationOptions
+                {
+                    constants: &constants,
+                    zero_initialize_workgroup_memory: true,
+                },
             }),
             multiview_mask: None,
             cache: None,
@@ -170,22 +210,22 @@ impl RenderData
         (bind_group_layout, render_pipeline)
     }
 
-    fn create_buffers(device: &wgpu::Device, num_vertices: u64, num_indices: u64) -> (wgpu::Buffer, wgpu::Buffer)
+    fn create_buffers(device: &wgpu::Device, stride: u64, num_vertices: u64, num_indices: u64) -> (wgpu::Buffer, wgpu::Buffer)
     {
-        let vertices_len = num_vertices * std::mem::size_of::<Vertex>() as u64;
+        let vertices_len = num_vertices * stride;
         let vertex_buf_descr = wgpu::BufferDescriptor
         {
-            label: None,
+            label: Some("gru ui vertices"),
             size: vertices_len,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::VERTEX,
             mapped_at_creation: false,
         };
         let vertex_buf = device.create_buffer(&vertex_buf_descr);
 
-        let indices_len = num_indices * std::mem::size_of::<u16>() as u64;
+        let indices_len = (num_indices * std::mem::size_of::<u16>() as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
         let index_buf_descr = wgpu::BufferDescriptor
         {
-            label: None,
+            label: Some("gru ui indices"),
             size: indices_len,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::INDEX,
             mapped_at_creation: false,
@@ -195,18 +235,18 @@ impl RenderData
         (vertex_buf, index_buf)
     }
 
-    fn create_glyphs(graphics: &Graphics, data: Option<&Vec<Vec<u8>>>) -> (wgpu::Texture, wgpu::TextureView)
+    fn create_atlas(graphics: &Graphics, size: u32, layers: &[&[u8]]) -> (wgpu::Texture, wgpu::TextureView)
     {
-        let mut layer_count = data.map(|layers| layers.len() as u32).unwrap_or(1);
+        let mut layer_count = layers.len().max(1) as u32;
         if graphics.backend() == wgpu::Backend::Gl { layer_count = layer_count.max(2); } //GL does not like TextureArray with 1 element
 
-        let glyphs_descr = wgpu::TextureDescriptor
+        let atlas_descr = wgpu::TextureDescriptor
         {
             label: None,
             size: wgpu::Extent3d
             {
-                width: gru_ui::paint::TEXTURE_SIZE,
-                height: gru_ui::paint::TEXTURE_SIZE,
+                width: size,
+                height: size,
                 depth_or_array_layers: layer_count,
             },
             mip_level_count: 1,
@@ -216,22 +256,18 @@ impl RenderData
             usage: wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         };
-        let glyphs = match data
+        let atlas = if layers.is_empty() { graphics.device.create_texture(&atlas_descr) } else
         {
-            None => graphics.device.create_texture(&glyphs_descr),
-            Some(layers) =>
+            use wgpu::util::DeviceExt;
+            let layer_size = size.pow(2) as usize;
+            let mut data = vec![0; layer_count as usize * layer_size];
+            for (i, layer) in layers.iter().enumerate()
             {
-                use wgpu::util::DeviceExt;
-                let layer_size = gru_ui::paint::TEXTURE_SIZE.pow(2) as usize;
-                let mut data = vec![0; layer_count as usize * layer_size];
-                for i in 0..layers.len()
-                {
-                    let a = i * layer_size;
-                    let b = a + layer_size;
-                    data[a..b].copy_from_slice(&layers[i]);
-                }
-                graphics.device.create_texture_with_data(&graphics.queue, &glyphs_descr, wgpu::util::TextureDataOrder::LayerMajor, &data)
-            },
+                let a = i * layer_size;
+                let b = a + layer_size;
+                data[a..b].copy_from_slice(layer);
+            }
+            graphics.device.create_texture_with_data(&graphics.queue, &atlas_descr, wgpu::util::TextureDataOrder::LayerMajor, &data)
         };
         let view_descr = wgpu::TextureViewDescriptor
         {
@@ -245,41 +281,69 @@ impl RenderData
             base_array_layer: 0,
             array_layer_count: Some(layer_count),
         };
-        let glyphs_view = glyphs.create_view(&view_descr);
+        let atlas_view = atlas.create_view(&view_descr);
 
-        (glyphs, glyphs_view)
+        (atlas, atlas_view)
     }
 
-    fn create_bind_group(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout, glyphs_view: &wgpu::TextureView, sampler: &wgpu::Sampler) -> wgpu::BindGroup
+    //groups the square font layers by size into one texture array each, more sizes than atlas bindings are an error
+    fn create_glyphs(graphics: &Graphics, data: Option<&Vec<Vec<u8>>>) -> Result<(Vec<(wgpu::Texture, wgpu::TextureView)>, Vec<i32>)>
     {
+        let mut sizes: Vec<u32> = Vec::new();
+        let mut groups: Vec<Vec<&[u8]>> = Vec::new();
+        let mut layer_map = Vec::new();
+        for layer in data.into_iter().flatten()
+        {
+            let size = (layer.len() as f64).sqrt() as u32;
+            let atlas = match sizes.iter().position(|&s| s == size)
+            {
+                Some(atlas) => atlas,
+                None =>
+                {
+                    sizes.push(size);
+                    groups.push(Vec::new());
+                    sizes.len() - 1
+                },
+            };
+            layer_map.push(((atlas as i32) << 16) | groups[atlas].len() as i32);
+            groups[atlas].push(layer);
+        }
+        if sizes.len() > MAX_ATLASES { return Err(Error::GlyphAtlases { sizes: sizes.len(), max: MAX_ATLASES }); }
+        //unused atlas slots get an empty placeholder
+        let mut atlases: Vec<_> = sizes.iter().zip(&groups).map(|(&size, layers)| Self::create_atlas(graphics, size, layers)).collect();
+        while atlases.len() < MAX_ATLASES { atlases.push(Self::create_atlas(graphics, 1, &[])); }
+
+        Ok((atlases, layer_map))
+    }
+
+    fn create_bind_group(device: &wgpu::Device, bind_group_layout: &wgpu::BindGroupLayout, atlases: &[(wgpu::Texture, wgpu::TextureView)], samplers: &[wgpu::Sampler; 2]) -> wgpu::BindGroup
+    {
+        let mut entries: Vec<_> = atlases.iter().enumerate().map(|(i, (_, view))| wgpu::BindGroupEntry
+        {
+            binding: i as u32,
+            resource: wgpu::BindingResource::TextureView(view),
+        }).collect();
+        entries.extend(samplers.iter().enumerate().map(|(i, sampler)| wgpu::BindGroupEntry
+        {
+            binding: (MAX_ATLASES + i) as u32,
+            resource: wgpu::BindingResource::Sampler(sampler),
+        }));
         let bind_group_descr = wgpu::BindGroupDescriptor
         {
             label: None,
             layout: bind_group_layout,
-            entries:
-            &[
-                wgpu::BindGroupEntry
-                {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(glyphs_view),
-                },
-                wgpu::BindGroupEntry
-                {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(sampler),
-                },
-            ]
+            entries: &entries,
         };
         device.create_bind_group(&bind_group_descr)
     }
 
-    pub(crate) fn new(graphics: &Graphics, depth_format: Option<wgpu::TextureFormat>) -> Self
+    pub(crate) fn new(graphics: &Graphics, depth_format: Option<wgpu::TextureFormat>, alpha_mode: AlphaMode, (len_vertices, len_indices): (u64, u64)) -> Self
     {
-        let (bind_group_layout, render_pipeline) = Self::create_pipeline(&graphics.device, graphics.view_format(), depth_format);
-        let (vertex_buf, index_buf) = Self::create_buffers(&graphics.device, 1, 1);
-        let (len_vertices, len_indices, num_indices) = (1, 1, 0);
+        let (bind_group_layout, render_pipeline) = Self::create_pipeline(&graphics.device, graphics.view_format(), graphics.sample_count(), depth_format, alpha_mode, None);
+        let (len_vertices, len_indices, num_indices) = (len_vertices.max(1), len_indices.max(1), 0);
+        let (vertex_buf, index_buf) = Self::create_buffers(&graphics.device, Self::stride(None), len_vertices, len_indices);
         let glyphs_version = None;
-        let (glyphs, glyphs_view) = Self::create_glyphs(graphics, None);
+        let (atlases, layer_map) = Self::create_glyphs(graphics, None).unwrap(); //no layers, can not exceed the atlases
         let sampler_descr = wgpu::SamplerDescriptor
         {
             label: None,
@@ -295,88 +359,147 @@ impl RenderData
             anisotropy_clamp: 1,
             border_color: None,
         };
-        let glyphs_sampler = graphics.device.create_sampler(&sampler_descr);
-        let bind_group = Self::create_bind_group(&graphics.device, &bind_group_layout, &glyphs_view, &glyphs_sampler);
+        let nearest_descr = wgpu::SamplerDescriptor { mag_filter: wgpu::FilterMode::Nearest, min_filter: wgpu::FilterMode::Nearest, ..sampler_descr.clone() };
+        let glyphs_samplers = [graphics.device.create_sampler(&sampler_descr), graphics.device.create_sampler(&nearest_descr)];
+        let bind_group = Self::create_bind_group(&graphics.device, &bind_group_layout, &atlases, &glyphs_samplers);
+
+        Self { depth_format, alpha_mode, custom: None, upload: false, bind_group_layout, render_pipeline, vertex_buf, len_vertices, index_buf, len_indices, num_indices, glyphs_version, atlases, layer_map, nearest_layers: Vec::new(), glyphs_samplers, bind_group }
+    }
+
+    //replaces the vertex layout and shader, None restores the builtin ones
+    //errors if the extra size breaks the copy alignment
+    pub fn set_custom_vertex(&mut self, graphics: &Graphics, custom: Option<CustomVertex>) -> Result<()>
+    {
+        if let Some(custom) = &custom && custom.size % wgpu::COPY_BUFFER_ALIGNMENT != 0 { return Err(Error::Buffer(BufferError::Unaligned { offset: 0, len: custom.size })); }
+        let (bind_group_layout, render_pipeline) = Self::create_pipeline(&graphics.device, graphics.view_format(), graphics.sample_count(), self.depth_format, self.alpha_mode, custom.as_ref());
+        let (vertex_buf, index_buf) = Self::create_buffers(&graphics.device, Self::stride(custom.as_ref()), self.len_vertices, self.len_indices);
+        self.bind_group = Self::create_bind_group(&graphics.device, &bind_group_layout, &self.atlases, &self.glyphs_samplers);
+        self.bind_group_layout = bind_group_layout;
+        self.render_pipeline = render_pipeline;
+        self.vertex_buf = vertex_buf;
+        self.index_buf = index_buf;
+        self.custom = custom;
+        self.num_indices = 0;
+        self.upload = true;
+        Ok(())
+    }
 
-        Self { bind_group_layout, render_pipeline, vertex_buf, len_vertices, index_buf, len_indices, num_indices, glyphs_version, glyphs, glyphs_view, glyphs_sampler, bind_group }
+    //per font layer (the layer of the paint vertices tex_coords), e.g. Nearest for crisp pixel fonts next to smooth ones
+    pub fn set_layer_filter(&mut self, layer: u32, filter: wgpu::FilterMode)
+    {
+        let nearest = filter == wgpu::FilterMode::Nearest;
+        if nearest == self.nearest_layers.contains(&layer) { return; }
+        if nearest { self.nearest_layers.push(layer); } else { self.nearest_layers.retain(|&l| l != layer); }
+        self.upload = true;
     }
 
-    pub fn update(&mut self, graphics: &Graphics, data: &gru_ui::paint::Frame)
+    //errors if the fonts need more atlases than ui.wgsl binds, the previous glyphs are kept then
+    pub fn update(&mut self, graphics: &Graphics, data: &gru_ui::paint::Frame) -> Result<()>
     {
-        if let Some(size) = graphics.surface_size() && data.new
+        //update glyphs if new
+        if self.glyphs_version != Some(data.font_version)
+        {
+            self.glyphs_version = Some(data.font_version); //a failed version is not retried, the old glyphs stay
+            let (atlases, layer_map) = Self::create_glyphs(graphics, Some(data.font_data))?;
+            let bind_group = Self::create_bind_group(&graphics.device, &self.bind_group_layout, &atlases, &self.glyphs_samplers);
+
+            self.atlases = atlases;
+            self.layer_map = layer_map;
+            self.bind_group = bind_group;
+        }
+        if let Some(size) = graphics.surface_size() && (data.new || self.upload)
         {
+            self.upload = false;
             let size = Vec2(size.0 as f32, size.1 as f32);
             //convert vertices
-            let mut vertices = Vec::with_capacity(data.vertices.len());
-            for vertex in data.vertices
+            let stride = Self::stride(self.custom.as_ref()) as usize;
+            let mut vertex_bytes = Vec::with_capacity(data.vertices.len() * stride);
+            for (i, vertex) in data.vertices.iter().enumerate()
             {
-                let position = Vec2::from(vertex.position).component_div(size) * 2.0 - Vec2(1.0, 1.0);
-                let position = position.component_mul(Vec2(1.0, -1.0)); //vulkan -> wgpu coordinates
+                let position = coords::screen_to_ndc(Vec2::from(vertex.position), size);
                 let color = vertex.color.to_normalized_linear().into();
                 let (tex_coords, layer) = match vertex.tex_coords
                 {
-                    Some((u, v, l)) => ((u, v).into(), l as i32),
+                    Some((u, v, l)) =>
+                    {
+                        let nearest = if self.nearest_layers.contains(&(l as u32)) { 1 << 30 } else { 0 };
+                        ((u, v).into(), self.layer_map.get(l as usize).copied().unwrap_or(0) | nearest)
+                    },
                     None => ((0.0_f32, 0.0).into(), -1)
                 };
                 let vertex = Vertex { position, color, tex_coords, layer };
-                vertices.push(vertex);
+                let bytes = unsafe
+                {
+                    let ptr = &vertex as *const Vertex as *const u8;
+                    std::slice::from_raw_parts(ptr, std::mem::size_of::<Vertex>())
+                };
+                vertex_bytes.extend_from_slice(bytes);
+                if let Some(custom) = self.custom.as_mut()
+                {
+                    let start = vertex_bytes.len();
+                    vertex_bytes.resize(start + custom.size as usize, 0);
+                    (custom.fill)(data, i, &mut vertex_bytes[start..]);
+                }
             }
+            let num_vertices = data.vertices.len() as u64;
             //create new buffer if too small
-            if vertices.len() as u64 > self.len_vertices || data.indices.len() as u64 > self.len_indices
+            if num_vertices > self.len_vertices || data.indices.len() as u64 > self.len_indices
             {
-                let (vertex_buf, index_buf) = Self::create_buffers(&graphics.device, vertices.len() as u64, data.indices.len() as u64);
+                let (vertex_buf, index_buf) = Self::create_buffers(&graphics.device, stride as u64, num_vertices, data.indices.len() as u64);
                 self.vertex_buf = vertex_buf;
                 self.index_buf = index_buf;
-                self.len_vertices = vertices.len() as u64;
+                self.len_vertices = num_vertices;
                 self.len_indices = data.indices.len() as u64;
             }
-            //fill buffer
-            let vertex_bytes = unsafe
-            {
-                let ptr = vertices.as_ptr() as *const u8;
-                std::slice::from_raw_parts(ptr, vertices.len() * std::mem::size_of::<Vertex>())
-            };
-            graphics.queue.write_buffer(&self.vertex_buf, 0, vertex_bytes);
-            
+            //fill buffer, a failed write skips the frame's ui instead of panicking
             let index_bytes = unsafe
             {
                 let ptr = data.indices.as_ptr() as *const u8;
                 std::slice::from_raw_parts(ptr, data.indices.len() * std::mem::size_of::<u16>())
             };
-            graphics.queue.write_buffer(&self.index_buf, 0, index_bytes);
+            let written = graphics.write_buffer_checked(&self.vertex_buf, 0, &vertex_bytes).and_then(|()|
+            {
+                if index_bytes.len() % 4 == 0 { return graphics.write_buffer_checked(&self.index_buf, 0, index_bytes); }
+                //pad odd index counts to the copy alignment
+                let mut padded = index_bytes.to_vec();
+                padded.resize(index_bytes.len().next_multiple_of(4), 0);
+                graphics.write_buffer_checked(&self.index_buf, 0, &padded)
+            });
+            if let Err(err) = written
+            {
+                log::error!("ui upload: {err}");
+                self.num_indices = 0;
+                return Ok(());
+            }
             self.num_indices = data.indices.len() as u32;
             //trigger gpu upload
             graphics.queue.submit([]);
         }
-        //update glyphs if new
-        if self.glyphs_version != Some(data.font_version)
-        {
-            let (glyphs, glyphs_view) = Self::create_glyphs(graphics, Some(data.font_data));
-            let bind_group = Self::create_bind_group(&graphics.device, &self.bind_group_layout, &glyphs_view, &self.glyphs_sampler);
-
-            self.glyphs_version = Some(data.font_version);
-            self.glyphs = glyphs;
-            self.glyphs_view = glyphs_view;
-            self.bind_group = bind_group;
-        }
+        Ok(())
     }
 
     pub fn render(&self, render_pass: &mut wgpu::RenderPass)
     {
         if self.num_indices > 0
         {
+            #[cfg(feature = "test-capture")]
+            crate::capture::record(|| crate::capture::Op::Ui { indices: self.num_indices });
+            render_pass.push_debug_group("gru ui");
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buf.slice(..));
             render_pass.set_index_buffer(self.index_buf.slice(..), wgpu::IndexFormat::Uint16);
             render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+            render_pass.pop_debug_group();
         }
     }
 }
 
-pub fn ui_config(window: &Window, scale: f32) -> gru_ui::UiConfig
+//sized like the render target, the window size can briefly differ during resizes
+pub fn ui_config(graphics: &Graphics, window: &Window, scale: f32) -> gru_ui::UiConfig
 {
-    let size: (f32, f32) = window.inner_size().into();
+    let (width, height) = graphics.surface_size().unwrap_or_else(|| window.inner_size().into());
+    let size = (width as f32, height as f32);
     let display_scale_factor = window.scale_factor() as f32;
     gru_ui::UiConfig
     {